@@ -0,0 +1,5 @@
+// Embedded migrations are only read at compile time, rebuild when they change.
+fn main() {
+    println!("cargo:rerun-if-changed=src/iter5/migrations");
+    println!("cargo:rerun-if-changed=src/iter5/sqlite_migrations");
+}
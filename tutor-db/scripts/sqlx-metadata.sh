@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+# Query metadata of the sqlx macros, kept in tutor-db/.sqlx so that builds need no database.
+#
+#   scripts/sqlx-metadata.sh prepare   regenerates .sqlx from the database of DATABASE_URL
+#   scripts/sqlx-metadata.sh check     fails when .sqlx differs from what prepare would write
+#
+# Both need the database up with every migration applied. This is what `cargo sqlx prepare`
+# and `cargo sqlx prepare --check` do, without having to install sqlx-cli.
+set -euo pipefail
+
+crate_dir="$(cd "$(dirname "$0")/.." && pwd)"
+metadata_dir="$crate_dir/.sqlx"
+# A target directory of its own, so the main build is not invalidated on every run
+target_dir="$crate_dir/../target/sqlx-metadata"
+
+generate() {
+    local out_dir="$1"
+    rm -rf "$out_dir"
+    mkdir -p "$out_dir"
+    # The macros only write metadata when they expand, so tutor-db is always recompiled
+    cargo clean --quiet --package tutor-db --target-dir "$target_dir"
+    (cd "$crate_dir" && SQLX_OFFLINE=false SQLX_OFFLINE_DIR="$out_dir" SQLX_TMP="$target_dir" \
+        cargo check --quiet --package tutor-db --all-targets --all-features --target-dir "$target_dir")
+}
+
+case "${1:-}" in
+    prepare)
+        generate "$metadata_dir"
+        echo "Wrote $(ls "$metadata_dir" | wc -l) queries to $metadata_dir"
+        ;;
+    check)
+        fresh_dir="$target_dir/fresh"
+        generate "$fresh_dir"
+        if ! diff -r "$metadata_dir" "$fresh_dir" > /dev/null; then
+            diff -rq "$metadata_dir" "$fresh_dir" | sed "s|$fresh_dir|prepared|g" >&2 || true
+            echo "Query metadata in .sqlx is stale, run scripts/sqlx-metadata.sh prepare" >&2
+            exit 1
+        fi
+        echo "Query metadata in .sqlx is up to date"
+        ;;
+    *)
+        echo "Usage: $0 prepare|check" >&2
+        exit 2
+        ;;
+esac
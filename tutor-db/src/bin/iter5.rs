@@ -1,11 +1,11 @@
 use std::{env, io};
-use std::sync::Mutex;
 use actix_web::{App, HttpServer, web};
+use actix_web::dev::Service;
 use dotenv::dotenv;
 use log::{debug, error};
-use sqlx::PgPool;
 use crate::errors::EzyTutorError;
-use crate::routes::{course_routes, general_routes, tutor_routes};
+use crate::routes::{course_routes, general_routes, path_routes, tutor_routes};
+use crate::schema::MigrateCommand;
 use crate::state::AppState;
 
 #[path = "../iter5/handlers/mod.rs"]
@@ -22,6 +22,28 @@ mod models;
 mod dbaccess;
 #[path = "../iter5/errors.rs"]
 mod errors;
+#[path = "../iter5/i18n.rs"]
+mod i18n;
+#[path = "../iter5/slug.rs"]
+mod slug;
+#[path = "../iter5/patch.rs"]
+mod patch;
+#[path = "../iter5/precondition.rs"]
+mod precondition;
+#[path = "../iter5/markdown.rs"]
+mod markdown;
+#[path = "../iter5/schema.rs"]
+mod schema;
+#[path = "../iter5/repository/mod.rs"]
+mod repository;
+#[path = "../iter5/pool.rs"]
+mod pool;
+#[path = "../iter5/replica.rs"]
+mod replica;
+
+fn fail(error: EzyTutorError) -> io::Error {
+    io::Error::other(format!("{:?}", error))
+}
 
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
@@ -29,17 +51,76 @@ async fn main() -> io::Result<()> {
     env_logger::init();
 
     let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-    let db_pool = PgPool::connect(&database_url).await.unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("migrate") => Some(MigrateCommand::parse(&args[1..]).map_err(fail)?),
+        _ => None,
+    };
+    // `render-html` renders the stored profile and description HTML again, after loading the seed
+    let render_html = args.first().map(String::as_str) == Some("render-html");
+    // Pending migrations are applied at startup unless MIGRATE_ON_STARTUP=false
+    let apply_pending = env::var("MIGRATE_ON_STARTUP").map(|value| value != "false").unwrap_or(true);
+    // Writes to tutors and courses need If-Match unless REQUIRE_IF_MATCH=false
+    let require_if_match = env::var("REQUIRE_IF_MATCH").map(|value| value != "false").unwrap_or(true);
+    let health_check_response = "I'm good, you have asked already".to_string();
 
-    let shared_data = web::Data::new(AppState {
-        health_check_response: "I'm good, you have asked already".to_string(),
-        visit_count: Mutex::new(0),
-        db: db_pool
-    });
+    let app_state = match database_url.split(':').next() {
+        #[cfg(feature = "sqlite")]
+        Some("sqlite") => {
+            let db_pool = repository::sqlite::connect(&database_url).await.map_err(fail)?;
+            if let Some(command) = command {
+                return schema::migrate(&schema::SQLITE_MIGRATOR, &db_pool, command).await.map_err(fail);
+            }
+            if render_html {
+                return Err(io::Error::other("render-html is only needed for the Postgres seed"));
+            }
+            schema::prepare(&schema::SQLITE_MIGRATOR, &db_pool, apply_pending).await.map_err(fail)?;
+            AppState::sqlite(health_check_response, db_pool)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        Some("sqlite") => return Err(io::Error::other("SQLite databases need the sqlite feature")),
+        _ => {
+            let pool_config = pool::PoolConfig::from_env().map_err(fail)?;
+            let db_pool = pool::connect(&database_url, &pool_config).await.map_err(fail)?;
+            if command == Some(MigrateCommand::Baseline) {
+                return schema::baseline(&schema::MIGRATOR, &db_pool).await.map_err(fail);
+            }
+            schema::check_baselined(&db_pool).await.map_err(fail)?;
+            if let Some(command) = command {
+                return schema::migrate(&schema::MIGRATOR, &db_pool, command).await.map_err(fail);
+            }
+            schema::prepare(&schema::MIGRATOR, &db_pool, apply_pending).await.map_err(fail)?;
+            if render_html {
+                let tutors = dbaccess::tutor::render_profiles(&db_pool).await.map_err(fail)?;
+                let courses = dbaccess::course::render_descriptions(&db_pool).await.map_err(fail)?;
+                println!("Rendered {} tutor profiles and {} course descriptions", tutors, courses);
+                return Ok(());
+            }
+            let app_state = AppState::new(health_check_response, db_pool);
+            // GET endpoints read from DATABASE_REPLICA_URL when it is set, once it has replayed
+            // the last write of the client
+            match env::var("DATABASE_REPLICA_URL") {
+                Ok(replica_url) => {
+                    let replica_pool = pool::connect(&replica_url, &pool_config).await.map_err(fail)?;
+                    app_state.with_replica_pool(replica_pool)
+                }
+                Err(_) => app_state,
+            }
+        }
+    };
+    let shared_data = web::Data::new(app_state.with_if_match_required(require_if_match));
 
     let app = move || {
         App::new()
             .app_data(shared_data.clone())
+            .wrap_fn(move |req, srv| {
+                let res = srv.call(req);
+                async move {
+                    let mut res = res.await?;
+                    replica::pin_after_write(&mut res).await;
+                    Ok(res)
+                }
+            })
             .app_data(web::JsonConfig::default().error_handler(|_e,_| {
                 EzyTutorError::InvalidInput("Please provide a valid JSON input".to_string()).into()
             }))
@@ -47,6 +128,7 @@ async fn main() -> io::Result<()> {
             .service(web::scope("/api/v1")
                 .configure(course_routes)
                 .configure(tutor_routes)
+                .configure(path_routes)
             )
     };
 
@@ -0,0 +1,216 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Acquire, PgExecutor, Postgres};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::booking::{AvailabilityException, AvailabilityExceptionDto, AvailabilityRule, BOOKED, CANCELLED, Lesson, OpenSlot, WeeklyAvailabilityDto};
+
+pub async fn is_known_timezone(db: impl PgExecutor<'_>, timezone: &str) -> Result<bool, EzyTutorError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1) as "known!""#,
+        timezone
+    ).fetch_one(db).await?)
+}
+
+pub async fn get_rules(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<AvailabilityRule>, EzyTutorError> {
+    Ok(sqlx::query_as!(AvailabilityRule,
+        r#"SELECT id,
+                tutor_id,
+                weekday,
+                start_time,
+                end_time,
+                timezone
+            FROM availability_rules WHERE tutor_id = $1
+            ORDER BY weekday, start_time"#,
+        tutor_id
+    ).fetch_all(db).await?)
+}
+
+pub async fn replace_rules(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, dto: WeeklyAvailabilityDto) -> Result<Vec<AvailabilityRule>, EzyTutorError> {
+    let weekdays: Vec<i16> = dto.rules.iter().map(|rule| rule.weekday).collect();
+    let start_times: Vec<_> = dto.rules.iter().map(|rule| rule.start_time).collect();
+    let end_times: Vec<_> = dto.rules.iter().map(|rule| rule.end_time).collect();
+
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        r#"DELETE FROM availability_rules WHERE tutor_id = $1"#,
+        tutor_id
+    ).execute(&mut *tx).await?;
+    sqlx::query!(
+        r#"INSERT INTO availability_rules (id, tutor_id, weekday, start_time, end_time, timezone)
+            SELECT uuid_generate_v4(), $1, rules.weekday, rules.start_time, rules.end_time, $2
+            FROM UNNEST($3::smallint[], $4::time[], $5::time[]) AS rules(weekday, start_time, end_time)"#,
+        tutor_id,
+        dto.timezone,
+        &weekdays,
+        &start_times,
+        &end_times,
+    ).execute(&mut *tx).await?;
+    let rules = get_rules(&mut *tx, tutor_id).await?;
+    tx.commit().await?;
+
+    Ok(rules)
+}
+
+pub async fn get_exceptions(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<AvailabilityException>, EzyTutorError> {
+    Ok(sqlx::query_as!(AvailabilityException,
+        r#"SELECT id,
+                tutor_id,
+                starts_at,
+                ends_at,
+                available,
+                reason
+            FROM availability_exceptions WHERE tutor_id = $1 AND ends_at > now()
+            ORDER BY starts_at"#,
+        tutor_id
+    ).fetch_all(db).await?)
+}
+
+pub async fn add_exception(db: impl PgExecutor<'_>, tutor_id: Uuid, dto: AvailabilityExceptionDto) -> Result<AvailabilityException, EzyTutorError> {
+    Ok(sqlx::query_as!(AvailabilityException,
+        r#"INSERT INTO availability_exceptions (id, tutor_id, starts_at, ends_at, available, reason)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING
+                id,
+                tutor_id,
+                starts_at,
+                ends_at,
+                available,
+                reason"#,
+        Uuid::new_v4(),
+        tutor_id,
+        dto.starts_at,
+        dto.ends_at,
+        dto.available,
+        dto.reason,
+    ).fetch_one(db).await?)
+}
+
+pub async fn delete_exception(db: impl PgExecutor<'_>, tutor_id: Uuid, exception_id: Uuid) -> Result<AvailabilityException, EzyTutorError> {
+    Ok(sqlx::query_as!(AvailabilityException,
+        r#"DELETE FROM availability_exceptions WHERE id = $1 AND tutor_id = $2
+            RETURNING
+                id,
+                tutor_id,
+                starts_at,
+                ends_at,
+                available,
+                reason"#,
+        exception_id,
+        tutor_id,
+    ).fetch_one(db).await?)
+}
+
+/// Splits the free time of the tutor between the `from` and `to` days of `timezone` into
+/// consecutive slots of `minutes`, leaving out booked lessons and slots already started.
+pub async fn get_open_slots(db: impl PgExecutor<'_>, tutor_id: Uuid, from: NaiveDate, to: NaiveDate, timezone: &str, minutes: i32) -> Result<Vec<OpenSlot>, EzyTutorError> {
+    Ok(sqlx::query_as!(OpenSlot,
+        r#"WITH free AS (
+            SELECT unnest(
+                tutor_availability($1, $2::date::timestamp AT TIME ZONE $4, ($3::date + 1)::timestamp AT TIME ZONE $4)
+                - coalesce((SELECT range_agg(tstzrange(starts_at, ends_at)) FROM lessons
+                            WHERE tutor_id = $1 AND status = $6), '{}')
+            ) AS period
+        ), slots AS (
+            SELECT generate_series(lower(period), upper(period) - make_interval(mins => $5), make_interval(mins => $5)) AS starts_at
+            FROM free
+        )
+        SELECT starts_at as "starts_at!",
+            starts_at + make_interval(mins => $5) as "ends_at!",
+            starts_at AT TIME ZONE $4 as "local_starts_at!",
+            (starts_at + make_interval(mins => $5)) AT TIME ZONE $4 as "local_ends_at!"
+        FROM slots
+        WHERE starts_at > now()
+        ORDER BY starts_at"#,
+        tutor_id,
+        from,
+        to,
+        timezone,
+        minutes,
+        BOOKED,
+    ).fetch_all(db).await?)
+}
+
+/// Books the lesson if the tutor is available for all of it. Overlapping bookings of the tutor
+/// or the student are refused by exclusion constraints and surface as conflicts.
+pub async fn book_lesson(db: impl PgExecutor<'_>, tutor_id: Uuid, student_id: Uuid, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> Result<Option<Lesson>, EzyTutorError> {
+    Ok(sqlx::query_as!(Lesson,
+        r#"INSERT INTO lessons (id, tutor_id, student_id, starts_at, ends_at, status)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE tutor_availability($2, $4, $5) @> tstzrange($4, $5)
+            RETURNING
+                id,
+                tutor_id,
+                student_id,
+                starts_at,
+                ends_at,
+                status,
+                created_at,
+                cancelled_at"#,
+        Uuid::new_v4(),
+        tutor_id,
+        student_id,
+        starts_at,
+        ends_at,
+        BOOKED,
+    ).fetch_optional(db).await?)
+}
+
+pub async fn get_upcoming_lessons(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<Lesson>, EzyTutorError> {
+    Ok(sqlx::query_as!(Lesson,
+        r#"SELECT id,
+                tutor_id,
+                student_id,
+                starts_at,
+                ends_at,
+                status,
+                created_at,
+                cancelled_at
+            FROM lessons WHERE tutor_id = $1 AND status = $2 AND ends_at > now()
+            ORDER BY starts_at"#,
+        tutor_id,
+        BOOKED,
+    ).fetch_all(db).await?)
+}
+
+/// Locks the lesson until the end of the transaction, if any, so it can not be changed while
+/// it is being looked at.
+pub async fn get_booked_lesson(db: impl PgExecutor<'_>, tutor_id: Uuid, lesson_id: Uuid) -> Result<Lesson, EzyTutorError> {
+    Ok(sqlx::query_as!(Lesson,
+        r#"SELECT id,
+                tutor_id,
+                student_id,
+                starts_at,
+                ends_at,
+                status,
+                created_at,
+                cancelled_at
+            FROM lessons WHERE id = $1 AND tutor_id = $2 AND status = $3
+            FOR UPDATE"#,
+        lesson_id,
+        tutor_id,
+        BOOKED,
+    ).fetch_one(db).await?)
+}
+
+pub async fn cancel_lesson(db: impl PgExecutor<'_>, tutor_id: Uuid, lesson_id: Uuid) -> Result<Lesson, EzyTutorError> {
+    Ok(sqlx::query_as!(Lesson,
+        r#"UPDATE lessons
+            SET status = $1,
+                cancelled_at = $2
+        WHERE id = $3 AND tutor_id = $4 AND status = $5
+        RETURNING
+                id,
+                tutor_id,
+                student_id,
+                starts_at,
+                ends_at,
+                status,
+                created_at,
+                cancelled_at"#,
+        CANCELLED,
+        Utc::now().naive_utc(),
+        lesson_id,
+        tutor_id,
+        BOOKED,
+    ).fetch_one(db).await?)
+}
@@ -0,0 +1,176 @@
+use sqlx::{Acquire, PgExecutor, Postgres};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::coupon::{Coupon, CouponRedemption, CreateCouponDto};
+use chrono::Utc;
+
+pub async fn get_coupons_by_tutor(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<Coupon>, EzyTutorError> {
+    Ok(sqlx::query_as!(Coupon,
+        r#"SELECT c.id,
+                c.tutor_id,
+                c.code,
+                c.discount_type,
+                c.amount,
+                c.expires_at,
+                c.max_redemptions,
+                c.redemptions,
+                COALESCE(array_agg(cc.course_id) FILTER (WHERE cc.course_id IS NOT NULL), '{}') as "course_ids!",
+                c.created_at,
+                c.updated_at,
+                c.deleted_at
+            FROM coupons c LEFT JOIN coupon_courses cc ON cc.coupon_id = c.id
+            WHERE c.tutor_id = $1 AND c.deleted_at is null
+            GROUP BY c.id
+            ORDER BY c.created_at"#,
+        tutor_id
+    ).fetch_all(db).await?)
+}
+
+pub async fn by_code(db: impl PgExecutor<'_>, tutor_id: Uuid, code: &str) -> Result<Coupon, EzyTutorError> {
+    Ok(sqlx::query_as!(Coupon,
+        r#"SELECT c.id,
+                c.tutor_id,
+                c.code,
+                c.discount_type,
+                c.amount,
+                c.expires_at,
+                c.max_redemptions,
+                c.redemptions,
+                COALESCE(array_agg(cc.course_id) FILTER (WHERE cc.course_id IS NOT NULL), '{}') as "course_ids!",
+                c.created_at,
+                c.updated_at,
+                c.deleted_at
+            FROM coupons c LEFT JOIN coupon_courses cc ON cc.coupon_id = c.id
+            WHERE c.tutor_id = $1 AND c.code = upper($2) AND c.deleted_at is null
+            GROUP BY c.id"#,
+        tutor_id,
+        code,
+    ).fetch_one(db).await?)
+}
+
+pub async fn create(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, dto: CreateCouponDto) -> Result<Coupon, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let register_time = Utc::now().naive_utc();
+    let coupon_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"INSERT INTO
+            coupons (
+                id,
+                tutor_id,
+                code,
+                discount_type,
+                amount,
+                expires_at,
+                max_redemptions,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+        coupon_id,
+        tutor_id,
+        dto.code,
+        dto.discount_type,
+        dto.amount,
+        dto.expires_at,
+        dto.max_redemptions,
+        &register_time,
+        &register_time,
+    ).execute(&mut *tx).await?;
+
+    let restricted = sqlx::query!(
+        r#"INSERT INTO coupon_courses (coupon_id, course_id)
+            SELECT $1, id FROM ezy_course_c4
+            WHERE id = ANY($2) AND tutor_id = $3 AND deleted_at is null"#,
+        coupon_id,
+        &dto.course_ids,
+        tutor_id,
+    ).execute(&mut *tx).await?.rows_affected();
+    let mut course_ids = dto.course_ids.clone();
+    course_ids.sort();
+    course_ids.dedup();
+    if restricted != course_ids.len() as u64 {
+        return Err(EzyTutorError::InvalidInput("Coupons can only be restricted to courses of the same tutor".to_string()));
+    }
+    tx.commit().await?;
+
+    Ok(Coupon {
+        id: coupon_id,
+        tutor_id,
+        code: dto.code,
+        discount_type: dto.discount_type,
+        amount: dto.amount,
+        expires_at: dto.expires_at,
+        max_redemptions: dto.max_redemptions,
+        redemptions: 0,
+        course_ids,
+        created_at: register_time,
+        updated_at: Some(register_time),
+        deleted_at: None,
+    })
+}
+
+pub async fn soft_delete(db: impl PgExecutor<'_>, tutor_id: Uuid, coupon_id: Uuid) -> Result<(), EzyTutorError> {
+    sqlx::query!(
+        r#"UPDATE coupons
+            SET deleted_at = $1
+        WHERE tutor_id = $2 AND id = $3 AND deleted_at is null
+        RETURNING id"#,
+        Utc::now().naive_utc(),
+        tutor_id,
+        coupon_id,
+    ).fetch_one(db).await?;
+
+    Ok(())
+}
+
+/// Consumes one redemption of the coupon. The limit and expiry are re-checked by the update
+/// itself, so concurrent redemptions can never go past `max_redemptions`.
+pub async fn redeem(db: impl Acquire<'_, Database = Postgres>, coupon_id: Uuid, course_id: Uuid, student_id: Uuid, price: i32, effective_price: i32) -> Result<CouponRedemption, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!(
+        r#"UPDATE coupons
+            SET redemptions = redemptions + 1,
+                updated_at = $2
+        WHERE id = $1
+            AND deleted_at is null
+            AND (expires_at is null OR expires_at > $2)
+            AND (max_redemptions is null OR redemptions < max_redemptions)
+        RETURNING id"#,
+        coupon_id,
+        now,
+    ).fetch_optional(&mut *tx).await?
+        .ok_or(EzyTutorError::Conflict("Coupon is expired or has no redemptions left".to_string()))?;
+
+    let redemption = sqlx::query_as!(CouponRedemption,
+        r#"INSERT INTO
+            coupon_redemptions (
+                id,
+                coupon_id,
+                course_id,
+                student_id,
+                price,
+                effective_price,
+                created_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        returning
+                id,
+                coupon_id,
+                course_id,
+                student_id,
+                price,
+                effective_price,
+                created_at"#,
+        Uuid::new_v4(),
+        coupon_id,
+        course_id,
+        student_id,
+        price,
+        effective_price,
+        now,
+    ).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(redemption)
+}
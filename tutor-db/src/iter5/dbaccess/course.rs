@@ -1,10 +1,15 @@
-use sqlx::PgPool;
+use sqlx::{Acquire, PgExecutor, Postgres};
 use uuid::Uuid;
 use crate::errors::EzyTutorError;
+use crate::dbaccess::slug;
+use crate::markdown;
 use crate::models::course::{Course, CreateCourseDto, UpdateCourseDto};
+use crate::models::course_tutor::{CO_TUTOR, OWNER};
+use crate::models::slug::SlugOwner;
+use crate::precondition::ExpectedVersion;
 use chrono::Utc;
 
-pub async fn get_courses_by_tutor(pool: &PgPool, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError> {
+pub async fn get_courses_by_tutor(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError> {
     let result = sqlx::query_as!(Course,
         r#"SELECT id,
                 tutor_id,
@@ -19,15 +24,45 @@ pub async fn get_courses_by_tutor(pool: &PgPool, tutor_id: Uuid) -> Result<Vec<C
                 posted_time,
                 created_at,
                 updated_at,
-                deleted_at
-            FROM ezy_course_c4 WHERE tutor_id = $1 and deleted_at is null"#,
+                deleted_at,
+                slug,
+                description_html,
+                version
+            FROM ezy_course_c4
+            WHERE id IN (SELECT course_id FROM course_tutors WHERE tutor_id = $1) and deleted_at is null"#,
         tutor_id
-    ).fetch_all(pool).await?;
+    ).fetch_all(db).await?;
 
     Ok(result)
 }
 
-pub async fn get_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
+pub async fn get_courses_by_ids(db: impl PgExecutor<'_>, course_ids: &[Uuid]) -> Result<Vec<Course>, EzyTutorError> {
+    let result = sqlx::query_as!(Course,
+        r#"SELECT id,
+                tutor_id,
+                name,
+                description,
+                format,
+                structure,
+                duration,
+                price,
+                language,
+                level,
+                posted_time,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                description_html,
+                version
+            FROM ezy_course_c4 WHERE id = ANY($1) and deleted_at is null"#,
+        course_ids
+    ).fetch_all(db).await?;
+
+    Ok(result)
+}
+
+pub async fn get_course(db: impl PgExecutor<'_>, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
     let course_row: Course = sqlx::query_as!(Course,
         r#"SELECT
                 id,
@@ -43,16 +78,23 @@ pub async fn get_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid) -> Resul
                 posted_time,
                 created_at,
                 updated_at,
-                deleted_at
-           FROM ezy_course_c4 WHERE tutor_id = $1 AND id = $2 and deleted_at is null"#,
+                deleted_at,
+                slug,
+                description_html,
+                version
+           FROM ezy_course_c4
+           WHERE id = $2 AND id IN (SELECT course_id FROM course_tutors WHERE tutor_id = $1) and deleted_at is null"#,
         tutor_id, course_id,
-    ).fetch_one(pool).await?;
+    ).fetch_one(db).await?;
 
     Ok(course_row)
 }
 
-pub async fn new_course(pool: &PgPool, course: CreateCourseDto) -> Result<Course, EzyTutorError> {
+pub async fn new_course(db: impl Acquire<'_, Database = Postgres>, course: CreateCourseDto) -> Result<Course, EzyTutorError> {
+    let mut tx = db.begin().await?;
     let register_time = Utc::now().naive_utc();
+    let id = Uuid::new_v4();
+    let slug = slug::available_slug(&mut tx, SlugOwner::Course(id), &course.name).await?;
     let inserted_course: Course = sqlx::query_as!(
         Course,
         r#"INSERT INTO
@@ -69,8 +111,10 @@ pub async fn new_course(pool: &PgPool, course: CreateCourseDto) -> Result<Course
                 level,
                 posted_time,
                 created_at,
-                updated_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7,  $8, $9, $10, $11, $12, $13)
+                updated_at,
+                slug,
+                description_html
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7,  $8, $9, $10, $11, $12, $13, $14, $15)
         returning
                 id,
                 tutor_id,
@@ -85,8 +129,11 @@ pub async fn new_course(pool: &PgPool, course: CreateCourseDto) -> Result<Course
                 posted_time,
                 created_at,
                 updated_at,
-                deleted_at"#,
-        Uuid::new_v4(),
+                deleted_at,
+                slug,
+                description_html,
+                version"#,
+        id,
         course.tutor_id,
         course.name,
         course.description,
@@ -99,13 +146,62 @@ pub async fn new_course(pool: &PgPool, course: CreateCourseDto) -> Result<Course
         &register_time,
         &register_time,
         &register_time,
-    ).fetch_one(pool).await?;
+        slug,
+        course.description.as_deref().map(markdown::to_html),
+    ).fetch_one(&mut *tx).await?;
+    slug::record_slug(&mut tx, SlugOwner::Course(id), &inserted_course.slug).await?;
+    sqlx::query!(
+        r#"INSERT INTO course_tutors (course_id, tutor_id, role, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)"#,
+        id,
+        inserted_course.tutor_id,
+        OWNER,
+        &register_time,
+    ).execute(&mut *tx).await?;
+    tx.commit().await?;
 
     Ok(inserted_course)
 }
 
+/// Locks the course and builds its new state from the current one with `changes`, so that
+/// nothing can be written in between.
+pub async fn update_course_with<F>(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, course_id: Uuid, changes: F) -> Result<Course, EzyTutorError>
+    where F: FnOnce(&Course) -> Result<UpdateCourseDto, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let current = sqlx::query_as!(
+        Course,
+        r#"SELECT id,
+                tutor_id,
+                name,
+                description,
+                format,
+                structure,
+                duration,
+                price,
+                language,
+                level,
+                posted_time,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                description_html,
+                version
+        FROM ezy_course_c4
+        WHERE id = $2 AND deleted_at is null
+            AND id IN (SELECT course_id FROM course_tutors WHERE tutor_id = $1 AND role = ANY($3))
+        FOR UPDATE"#,
+        tutor_id,
+        course_id,
+        &[OWNER.to_string(), CO_TUTOR.to_string()],
+    ).fetch_one(&mut *tx).await?;
+    let update_data = changes(&current)?;
+    let slug = if current.name == update_data.name {
+        current.slug
+    } else {
+        slug::available_slug(&mut tx, SlugOwner::Course(course_id), &update_data.name).await?
+    };
 
-pub async fn update_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid, update_data: UpdateCourseDto) -> Result<Course, EzyTutorError> {
     let updated_course: Course = sqlx::query_as!(
         Course,
         r#"UPDATE ezy_course_c4
@@ -117,8 +213,9 @@ pub async fn update_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid, updat
                 price = $6,
                 language = $7,
                 level = $8,
-                updated_at = $9
-        WHERE tutor_id = $10 AND id = $11 AND deleted_at is null
+                slug = $9,
+                description_html = $10
+        WHERE id = $11 AND deleted_at is null
         returning
                 id,
                 tutor_id,
@@ -133,7 +230,10 @@ pub async fn update_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid, updat
                 posted_time,
                 created_at,
                 updated_at,
-                deleted_at"#,
+                deleted_at,
+                slug,
+                description_html,
+                version"#,
         update_data.name,
         update_data.description,
         update_data.format,
@@ -142,15 +242,25 @@ pub async fn update_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid, updat
         update_data.price,
         update_data.language,
         update_data.level,
-        Utc::now().naive_utc(),
-        tutor_id,
+        slug,
+        update_data.description.as_deref().map(markdown::to_html),
         course_id,
-    ).fetch_one(pool).await?;
+    ).fetch_one(&mut *tx).await?;
+    slug::record_slug(&mut tx, SlugOwner::Course(course_id), &updated_course.slug).await?;
+    tx.commit().await?;
 
     Ok(updated_course)
 }
 
-pub async fn soft_delete_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
+pub async fn soft_delete_course(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, course_id: Uuid, expected: &ExpectedVersion) -> Result<Course, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let version = sqlx::query_scalar!(
+        r#"SELECT version FROM ezy_course_c4 WHERE tutor_id = $1 AND id = $2 and deleted_at is null FOR UPDATE"#,
+        tutor_id,
+        course_id,
+    ).fetch_one(&mut *tx).await?;
+    expected.check(version)?;
+
     let deleted_course: Course = sqlx::query_as!(
         Course,
         r#"UPDATE ezy_course_c4
@@ -170,11 +280,34 @@ pub async fn soft_delete_course(pool: &PgPool, tutor_id: Uuid, course_id: Uuid)
                 posted_time,
                 created_at,
                 updated_at,
-                deleted_at"#,
+                deleted_at,
+                slug,
+                description_html,
+                version"#,
         Utc::now().naive_utc(),
         tutor_id,
         course_id,
-    ).fetch_one(pool).await?;
+    ).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
 
     Ok(deleted_course)
-}
\ No newline at end of file
+}
+
+/// Renders `description_html` from `description` again for the courses where it does not
+/// match, such as rows loaded by `dbscripts/seed.sql`. Returns how many were rendered.
+pub async fn render_descriptions(db: impl Acquire<'_, Database = Postgres>) -> Result<u64, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let courses = sqlx::query!("SELECT id, description, description_html FROM ezy_course_c4 FOR UPDATE")
+        .fetch_all(&mut *tx).await?;
+    let mut rendered = 0;
+    for course in courses {
+        let description_html = course.description.as_deref().map(markdown::to_html);
+        if description_html != course.description_html {
+            sqlx::query!("UPDATE ezy_course_c4 SET description_html = $1 WHERE id = $2", description_html, course.id)
+                .execute(&mut *tx).await?;
+            rendered += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(rendered)
+}
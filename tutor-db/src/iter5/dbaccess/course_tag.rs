@@ -0,0 +1,59 @@
+use sqlx::{Acquire, PgExecutor, Postgres};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+
+pub async fn get_tags(db: impl PgExecutor<'_>, course_id: Uuid) -> Result<Vec<String>, EzyTutorError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT tag FROM course_tags WHERE course_id = $1 ORDER BY tag"#,
+        course_id
+    ).fetch_all(db).await?)
+}
+
+pub async fn replace_tags(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, tags: &[String]) -> Result<Vec<String>, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        r#"DELETE FROM course_tags WHERE course_id = $1"#,
+        course_id
+    ).execute(&mut *tx).await?;
+    sqlx::query!(
+        r#"INSERT INTO course_tags (course_id, tag)
+            SELECT $1, tag FROM UNNEST($2::varchar[]) AS tags(tag)"#,
+        course_id,
+        tags,
+    ).execute(&mut *tx).await?;
+    let tags = get_tags(&mut *tx, course_id).await?;
+    tx.commit().await?;
+
+    Ok(tags)
+}
+
+/// Scores every other active course against `course_id` and returns the best `limit` matches.
+/// Shared language, level and format and each shared tag add a fixed weight, and the trigram
+/// similarity (pg_trgm) of name and description adds up to 2 and 1 points respectively.
+pub async fn get_similar_course_scores(db: impl PgExecutor<'_>, course_id: Uuid, limit: i64) -> Result<Vec<(Uuid, f64)>, EzyTutorError> {
+    let scores = sqlx::query!(
+        r#"WITH target AS (
+            SELECT id, name, description, format, language, level
+            FROM ezy_course_c4 WHERE id = $1
+        )
+        SELECT c.id,
+            ( CASE WHEN lower(c.language) = lower(t.language) THEN 1.0 ELSE 0.0 END
+            + CASE WHEN lower(c.level) = lower(t.level) THEN 0.5 ELSE 0.0 END
+            + CASE WHEN lower(c.format) = lower(t.format) THEN 0.5 ELSE 0.0 END
+            + 0.75 * (
+                SELECT count(*) FROM course_tags ct
+                JOIN course_tags tt ON tt.tag = ct.tag AND tt.course_id = t.id
+                WHERE ct.course_id = c.id)
+            + 2.0 * similarity(c.name, t.name)
+            + similarity(coalesce(c.description, ''), coalesce(t.description, ''))
+            )::float8 as "score!"
+        FROM ezy_course_c4 c, target t
+        WHERE c.id <> t.id AND c.deleted_at is null
+        ORDER BY 2 DESC, c.id
+        LIMIT $2"#,
+        course_id,
+        limit,
+    ).fetch_all(db).await?;
+
+    Ok(scores.into_iter().map(|row| (row.id, row.score)).collect())
+}
@@ -0,0 +1,85 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::course::{CourseTranslation, UpsertCourseTranslationDto};
+use chrono::Utc;
+
+pub async fn get_translations(db: impl PgExecutor<'_>, course_id: Uuid) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+    Ok(sqlx::query_as!(CourseTranslation,
+        r#"SELECT course_id,
+                locale,
+                name,
+                description,
+                created_at,
+                updated_at
+            FROM course_translations WHERE course_id = $1
+            ORDER BY locale"#,
+        course_id
+    ).fetch_all(db).await?)
+}
+
+pub async fn get_translations_for_courses(db: impl PgExecutor<'_>, course_ids: &[Uuid], locales: &[String]) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+    if course_ids.is_empty() || locales.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(sqlx::query_as!(CourseTranslation,
+        r#"SELECT course_id,
+                locale,
+                name,
+                description,
+                created_at,
+                updated_at
+            FROM course_translations WHERE course_id = ANY($1) AND locale = ANY($2)"#,
+        course_ids,
+        locales,
+    ).fetch_all(db).await?)
+}
+
+pub async fn upsert_translation(db: impl PgExecutor<'_>, course_id: Uuid, locale: String, dto: UpsertCourseTranslationDto) -> Result<CourseTranslation, EzyTutorError> {
+    let register_time = Utc::now().naive_utc();
+    Ok(sqlx::query_as!(CourseTranslation,
+        r#"INSERT INTO
+            course_translations (
+                course_id,
+                locale,
+                name,
+                description,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (course_id, locale) DO UPDATE
+            SET name = EXCLUDED.name,
+                description = EXCLUDED.description,
+                updated_at = EXCLUDED.updated_at
+        returning
+                course_id,
+                locale,
+                name,
+                description,
+                created_at,
+                updated_at"#,
+        course_id,
+        locale,
+        dto.name,
+        dto.description,
+        &register_time,
+        &register_time,
+    ).fetch_one(db).await?)
+}
+
+pub async fn delete_translation(db: impl PgExecutor<'_>, course_id: Uuid, locale: String) -> Result<CourseTranslation, EzyTutorError> {
+    Ok(sqlx::query_as!(CourseTranslation,
+        r#"DELETE FROM course_translations
+        WHERE course_id = $1 AND locale = $2
+        returning
+                course_id,
+                locale,
+                name,
+                description,
+                created_at,
+                updated_at"#,
+        course_id,
+        locale,
+    ).fetch_one(db).await?)
+}
@@ -0,0 +1,93 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::course_tutor::{CourseTutor, OWNER};
+use chrono::Utc;
+
+/// Role of the tutor in an active course, `None` when the tutor does not teach it.
+pub async fn role_of(db: impl PgExecutor<'_>, course_id: Uuid, tutor_id: Uuid) -> Result<Option<String>, EzyTutorError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT ct.role FROM course_tutors ct
+            JOIN ezy_course_c4 c ON c.id = ct.course_id AND c.deleted_at is null
+            WHERE ct.course_id = $1 AND ct.tutor_id = $2"#,
+        course_id,
+        tutor_id,
+    ).fetch_optional(db).await?)
+}
+
+pub async fn get_course_tutors(db: impl PgExecutor<'_>, course_id: Uuid) -> Result<Vec<CourseTutor>, EzyTutorError> {
+    Ok(sqlx::query_as!(CourseTutor,
+        r#"SELECT course_id,
+                tutor_id,
+                role,
+                created_at,
+                updated_at
+            FROM course_tutors WHERE course_id = $1
+            ORDER BY role = 'owner' DESC, created_at"#,
+        course_id,
+    ).fetch_all(db).await?)
+}
+
+/// Every course membership of the given tutors, oldest course first.
+pub async fn get_memberships(db: impl PgExecutor<'_>, tutor_ids: &[Uuid]) -> Result<Vec<CourseTutor>, EzyTutorError> {
+    Ok(sqlx::query_as!(CourseTutor,
+        r#"SELECT ct.course_id,
+                ct.tutor_id,
+                ct.role,
+                ct.created_at,
+                ct.updated_at
+            FROM course_tutors ct
+            JOIN ezy_course_c4 c ON c.id = ct.course_id
+            WHERE ct.tutor_id = ANY($1)
+            ORDER BY c.created_at, c.id"#,
+        tutor_ids,
+    ).fetch_all(db).await?)
+}
+
+/// Adds the tutor to the course or changes its role. The owner can not be changed this way.
+pub async fn set_course_tutor(db: impl PgExecutor<'_>, course_id: Uuid, tutor_id: Uuid, role: &str) -> Result<CourseTutor, EzyTutorError> {
+    let register_time = Utc::now().naive_utc();
+    sqlx::query_as!(CourseTutor,
+        r#"INSERT INTO
+            course_tutors (
+                course_id,
+                tutor_id,
+                role,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (course_id, tutor_id) DO UPDATE
+            SET role = EXCLUDED.role,
+                updated_at = EXCLUDED.updated_at
+            WHERE course_tutors.role <> $6
+        returning
+                course_id,
+                tutor_id,
+                role,
+                created_at,
+                updated_at"#,
+        course_id,
+        tutor_id,
+        role,
+        &register_time,
+        &register_time,
+        OWNER,
+    ).fetch_optional(db).await?
+        .ok_or(EzyTutorError::InvalidInput("The course owner role can not be changed".to_string()))
+}
+
+pub async fn remove_course_tutor(db: impl PgExecutor<'_>, course_id: Uuid, tutor_id: Uuid) -> Result<CourseTutor, EzyTutorError> {
+    Ok(sqlx::query_as!(CourseTutor,
+        r#"DELETE FROM course_tutors
+        WHERE course_id = $1 AND tutor_id = $2 AND role <> $3
+        returning
+                course_id,
+                tutor_id,
+                role,
+                created_at,
+                updated_at"#,
+        course_id,
+        tutor_id,
+        OWNER,
+    ).fetch_one(db).await?)
+}
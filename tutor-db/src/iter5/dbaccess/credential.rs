@@ -0,0 +1,150 @@
+use chrono::Utc;
+use sqlx::{Acquire, PgExecutor, Postgres, Transaction};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::credential::{APPROVED, Credential, CredentialDto, PENDING};
+
+/// Derives the `verified` badge of the tutor from its approved credentials.
+pub async fn refresh_verified(tx: &mut Transaction<'_, Postgres>, tutor_id: Uuid) -> Result<(), EzyTutorError> {
+    sqlx::query!(
+        r#"UPDATE tutors
+            SET verified = EXISTS (SELECT 1 FROM tutor_credentials WHERE tutor_id = $1 AND status = $2)
+        WHERE id = $1"#,
+        tutor_id,
+        APPROVED,
+    ).execute(&mut **tx).await?;
+    Ok(())
+}
+
+pub async fn get_credentials(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<Vec<Credential>, EzyTutorError> {
+    Ok(sqlx::query_as!(Credential,
+        r#"SELECT id,
+                tutor_id,
+                kind,
+                title,
+                issuer,
+                issued_on,
+                attachment_urls,
+                status,
+                review_reason,
+                reviewed_at,
+                created_at,
+                updated_at
+            FROM tutor_credentials WHERE tutor_id = $1
+            ORDER BY created_at, id"#,
+        tutor_id
+    ).fetch_all(db).await?)
+}
+
+/// The review queue, oldest submission first.
+pub async fn get_pending_credentials(db: impl PgExecutor<'_>) -> Result<Vec<Credential>, EzyTutorError> {
+    Ok(sqlx::query_as!(Credential,
+        r#"SELECT c.id,
+                c.tutor_id,
+                c.kind,
+                c.title,
+                c.issuer,
+                c.issued_on,
+                c.attachment_urls,
+                c.status,
+                c.review_reason,
+                c.reviewed_at,
+                c.created_at,
+                c.updated_at
+            FROM tutor_credentials c
+            JOIN tutors t ON t.id = c.tutor_id AND t.deleted_at is null
+            WHERE c.status = $1
+            ORDER BY c.created_at, c.id"#,
+        PENDING
+    ).fetch_all(db).await?)
+}
+
+pub async fn add_credential(db: impl PgExecutor<'_>, tutor_id: Uuid, dto: CredentialDto) -> Result<Credential, EzyTutorError> {
+    Ok(sqlx::query_as!(Credential,
+        r#"INSERT INTO tutor_credentials (id, tutor_id, kind, title, issuer, issued_on, attachment_urls, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING
+                id,
+                tutor_id,
+                kind,
+                title,
+                issuer,
+                issued_on,
+                attachment_urls,
+                status,
+                review_reason,
+                reviewed_at,
+                created_at,
+                updated_at"#,
+        Uuid::new_v4(),
+        tutor_id,
+        dto.kind,
+        dto.title,
+        dto.issuer,
+        dto.issued_on,
+        &dto.attachment_urls,
+        PENDING,
+    ).fetch_one(db).await?)
+}
+
+/// Records the review and updates the tutor's badge in the same transaction. A credential can
+/// be reviewed again, so an approval can later be revoked.
+pub async fn review_credential(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, credential_id: Uuid, status: &str, reason: Option<String>) -> Result<Credential, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let credential = sqlx::query_as!(Credential,
+        r#"UPDATE tutor_credentials
+            SET status = $1,
+                review_reason = $2,
+                reviewed_at = $3,
+                updated_at = $3
+        WHERE id = $4 AND tutor_id = $5
+        RETURNING
+                id,
+                tutor_id,
+                kind,
+                title,
+                issuer,
+                issued_on,
+                attachment_urls,
+                status,
+                review_reason,
+                reviewed_at,
+                created_at,
+                updated_at"#,
+        status,
+        reason,
+        Utc::now().naive_utc(),
+        credential_id,
+        tutor_id,
+    ).fetch_one(&mut *tx).await?;
+    refresh_verified(&mut tx, tutor_id).await?;
+    tx.commit().await?;
+
+    Ok(credential)
+}
+
+pub async fn delete_credential(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, credential_id: Uuid) -> Result<Credential, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let credential = sqlx::query_as!(Credential,
+        r#"DELETE FROM tutor_credentials WHERE id = $1 AND tutor_id = $2
+            RETURNING
+                id,
+                tutor_id,
+                kind,
+                title,
+                issuer,
+                issued_on,
+                attachment_urls,
+                status,
+                review_reason,
+                reviewed_at,
+                created_at,
+                updated_at"#,
+        credential_id,
+        tutor_id,
+    ).fetch_one(&mut *tx).await?;
+    refresh_verified(&mut tx, tutor_id).await?;
+    tx.commit().await?;
+
+    Ok(credential)
+}
@@ -0,0 +1,220 @@
+use chrono::Utc;
+use sqlx::{Acquire, PgExecutor, Postgres};
+use uuid::Uuid;
+use crate::dbaccess::credential::refresh_verified;
+use crate::errors::EzyTutorError;
+use crate::models::course_tutor::{ASSISTANT, CO_TUTOR, OWNER};
+use crate::models::duplicate::{DUPLICATE_NAME_SIMILARITY, DUPLICATE_PIC_NAME_SIMILARITY, PossibleDuplicate, TutorMerge};
+use crate::models::tutor::Tutor;
+
+/// Active tutors with a name at least `DUPLICATE_NAME_SIMILARITY` similar to the new one, or
+/// `DUPLICATE_PIC_NAME_SIMILARITY` when they also have the same picture, most similar first.
+/// Candidates come from the trigram index through `%`.
+pub async fn find_duplicates(db: impl PgExecutor<'_>, name: &str, pic_url: &str) -> Result<Vec<Tutor>, EzyTutorError> {
+    Ok(sqlx::query_as!(Tutor, r#"
+        SELECT  id,
+                name,
+                pic_url,
+                profile,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        FROM tutors
+        WHERE deleted_at is null
+            AND lower(name) % lower($1)
+            AND similarity(lower(name), lower($1)) >= CASE WHEN pic_url = $2 THEN $4::real ELSE $3::real END
+        ORDER BY similarity(lower(name), lower($1)) DESC, id
+    "#,
+    name,
+    pic_url,
+    DUPLICATE_NAME_SIMILARITY,
+    DUPLICATE_PIC_NAME_SIMILARITY,
+    ).fetch_all(db).await?)
+}
+
+/// Every pair of active tutors that `find_duplicates` would have flagged. Pairs are only
+/// looked for among similar names, so tutors sharing a placeholder picture do not pair up.
+pub async fn get_possible_duplicates(db: impl PgExecutor<'_>) -> Result<Vec<PossibleDuplicate>, EzyTutorError> {
+    Ok(sqlx::query_as!(PossibleDuplicate, r#"
+        SELECT  t.id as tutor_id,
+                t.name as tutor_name,
+                d.id as duplicate_id,
+                d.name as duplicate_name,
+                similarity(lower(t.name), lower(d.name)) as "name_similarity!",
+                t.pic_url = d.pic_url as "same_pic_url!"
+        FROM tutors t
+        JOIN tutors d ON (t.created_at, t.id) < (d.created_at, d.id)
+            AND lower(t.name) % lower(d.name)
+        WHERE t.deleted_at is null AND d.deleted_at is null
+            AND similarity(lower(t.name), lower(d.name)) >= CASE WHEN t.pic_url = d.pic_url THEN $2::real ELSE $1::real END
+        ORDER BY 5 DESC, t.created_at, d.created_at
+    "#,
+    DUPLICATE_NAME_SIMILARITY,
+    DUPLICATE_PIC_NAME_SIMILARITY,
+    ).fetch_all(db).await?)
+}
+
+/// Moves the course memberships, coupons, credentials and ratings of `duplicate_id` to
+/// `tutor_id` and soft deletes it, in one transaction. On courses both tutors belong to,
+/// `tutor_id` keeps the higher of the two roles, and a student who rated both keeps their
+/// latest rating. Fails with `Conflict` when both tutors have an active coupon with the same code.
+pub async fn merge(db: impl Acquire<'_, Database = Postgres>, tutor_id: Uuid, duplicate_id: Uuid) -> Result<TutorMerge, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    // Locked in id order, so that concurrent merges of the same pair can not deadlock
+    let locked = sqlx::query_scalar!(r#"
+        SELECT id FROM tutors WHERE id = ANY($1) AND deleted_at is null ORDER BY id FOR UPDATE
+        "#,
+        &[tutor_id, duplicate_id],
+    ).fetch_all(&mut *tx).await?;
+    if locked.len() != 2 {
+        return Err(EzyTutorError::NotFound("Requested resource not found".to_string()));
+    }
+    let clashing_codes = sqlx::query_scalar!(r#"
+        SELECT d.code FROM coupons d
+        JOIN coupons t ON t.tutor_id = $1 AND t.code = d.code AND t.deleted_at is null
+        WHERE d.tutor_id = $2 AND d.deleted_at is null
+        ORDER BY d.code
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).fetch_all(&mut *tx).await?;
+    if !clashing_codes.is_empty() {
+        return Err(EzyTutorError::Conflict(format!("Both tutors have the coupons {}", clashing_codes.join(", "))));
+    }
+
+    let shared = sqlx::query!(r#"
+        DELETE FROM course_tutors d
+        WHERE d.tutor_id = $1
+            AND EXISTS (SELECT 1 FROM course_tutors t WHERE t.course_id = d.course_id AND t.tutor_id = $2)
+        RETURNING course_id, role
+        "#,
+        duplicate_id,
+        tutor_id,
+    ).fetch_all(&mut *tx).await?;
+    let shared_courses: Vec<Uuid> = shared.iter().map(|membership| membership.course_id).collect();
+    let shared_roles: Vec<String> = shared.into_iter().map(|membership| membership.role).collect();
+    sqlx::query!(r#"
+        UPDATE course_tutors t
+          SET   role = d.role,
+                updated_at = now()
+        FROM UNNEST($2::uuid[], $3::varchar[]) AS d(course_id, role)
+        WHERE t.course_id = d.course_id AND t.tutor_id = $1
+            AND array_position($4::varchar[], d.role) < array_position($4::varchar[], t.role)
+        "#,
+        tutor_id,
+        &shared_courses,
+        &shared_roles,
+        &[OWNER.to_string(), CO_TUTOR.to_string(), ASSISTANT.to_string()],
+    ).execute(&mut *tx).await?;
+    let mut moved_courses = sqlx::query_scalar!(r#"
+        UPDATE course_tutors
+          SET   tutor_id = $1,
+                updated_at = now()
+        WHERE tutor_id = $2
+        RETURNING course_id
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).fetch_all(&mut *tx).await?;
+    moved_courses.extend(shared_courses);
+    moved_courses.sort();
+    sqlx::query!(r#"
+        UPDATE ezy_course_c4
+          SET   tutor_id = $1
+        WHERE tutor_id = $2
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    sqlx::query!(r#"
+        UPDATE coupons
+          SET   tutor_id = $1,
+                updated_at = now()
+        WHERE tutor_id = $2
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    sqlx::query!(r#"
+        UPDATE tutor_credentials
+          SET   tutor_id = $1,
+                updated_at = now()
+        WHERE tutor_id = $2
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    sqlx::query!(r#"
+        UPDATE tutor_ratings t
+          SET   rating = d.rating,
+                updated_at = d.updated_at
+        FROM tutor_ratings d
+        WHERE t.tutor_id = $1 AND d.tutor_id = $2 AND d.student_id = t.student_id
+            AND d.updated_at > t.updated_at
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    sqlx::query!(r#"
+        DELETE FROM tutor_ratings d
+        WHERE d.tutor_id = $2
+            AND EXISTS (SELECT 1 FROM tutor_ratings t WHERE t.tutor_id = $1 AND t.student_id = d.student_id)
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    sqlx::query!(r#"
+        UPDATE tutor_ratings
+          SET   tutor_id = $1
+        WHERE tutor_id = $2
+        "#,
+        tutor_id,
+        duplicate_id,
+    ).execute(&mut *tx).await?;
+    refresh_verified(&mut tx, tutor_id).await?;
+    refresh_verified(&mut tx, duplicate_id).await?;
+
+    let duplicate = sqlx::query_as!(Tutor, r#"
+        UPDATE tutors
+          SET  deleted_at = $1
+        WHERE id = $2
+        RETURNING
+                id,
+                name,
+                pic_url,
+                profile,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        "#,
+        Utc::now().naive_utc(),
+        duplicate_id,
+    ).fetch_one(&mut *tx).await?;
+    let tutor = sqlx::query_as!(Tutor, r#"
+        SELECT  id,
+                name,
+                pic_url,
+                profile,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        FROM tutors WHERE id = $1
+        "#,
+        tutor_id,
+    ).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(TutorMerge { tutor, duplicate, moved_courses })
+}
@@ -0,0 +1,323 @@
+use sqlx::{Acquire, Postgres, Transaction};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::enrollment::{CourseCapacity, Enrollment, OFFER_HOLD_HOURS, WaitlistEntry};
+use chrono::{Duration, Utc};
+
+// Every operation that changes seat accounting runs in a transaction holding the course_seats
+// row lock, so enrollments, withdrawals and waitlist offers for the same course are serialized.
+// Courses without a course_seats row have unlimited capacity and never use the waitlist.
+
+async fn lock_capacity(tx: &mut Transaction<'_, Postgres>, course_id: Uuid) -> Result<Option<i32>, EzyTutorError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT capacity FROM course_seats WHERE course_id = $1 FOR UPDATE"#,
+        course_id
+    ).fetch_optional(&mut **tx).await?)
+}
+
+async fn seat_usage(tx: &mut Transaction<'_, Postgres>, course_id: Uuid) -> Result<(i64, i64), EzyTutorError> {
+    let usage = sqlx::query!(
+        r#"SELECT
+            (SELECT count(*) FROM enrollments WHERE course_id = $1 AND status = 'enrolled') as "enrolled!",
+            (SELECT count(*) FROM waitlist_entries WHERE course_id = $1 AND status = 'offered') as "held!""#,
+        course_id
+    ).fetch_one(&mut **tx).await?;
+
+    Ok((usage.enrolled, usage.held))
+}
+
+async fn capacity_of(tx: &mut Transaction<'_, Postgres>, course_id: Uuid, capacity: i32) -> Result<CourseCapacity, EzyTutorError> {
+    let (enrolled, held) = seat_usage(tx, course_id).await?;
+    Ok(CourseCapacity {
+        course_id,
+        capacity,
+        enrolled,
+        held,
+        available: (capacity as i64 - enrolled - held).max(0),
+    })
+}
+
+/// Expires the offers whose hold ran out and offers every free seat to the head of the waitlist.
+async fn settle_waitlist(tx: &mut Transaction<'_, Postgres>, course_id: Uuid, capacity: i32) -> Result<CourseCapacity, EzyTutorError> {
+    let now = Utc::now().naive_utc();
+    sqlx::query!(
+        r#"UPDATE waitlist_entries
+            SET status = 'expired',
+                updated_at = $2
+        WHERE course_id = $1 AND status = 'offered' AND hold_expires_at <= $2"#,
+        course_id,
+        now,
+    ).execute(&mut **tx).await?;
+
+    let capacity = capacity_of(tx, course_id, capacity).await?;
+    if capacity.available == 0 {
+        return Ok(capacity);
+    }
+
+    sqlx::query!(
+        r#"UPDATE waitlist_entries
+            SET status = 'offered',
+                offered_at = $2,
+                hold_expires_at = $3,
+                updated_at = $2
+        WHERE id IN (
+            SELECT id FROM waitlist_entries
+            WHERE course_id = $1 AND status = 'waiting'
+            ORDER BY created_at, id
+            LIMIT $4
+        )"#,
+        course_id,
+        now,
+        now + Duration::hours(OFFER_HOLD_HOURS),
+        capacity.available,
+    ).execute(&mut **tx).await?;
+
+    capacity_of(tx, course_id, capacity.capacity).await
+}
+
+async fn waitlist_entry(tx: &mut Transaction<'_, Postgres>, course_id: Uuid, student_id: Uuid) -> Result<WaitlistEntry, EzyTutorError> {
+    Ok(sqlx::query_as!(WaitlistEntry,
+        r#"SELECT * FROM (
+            SELECT id,
+                course_id,
+                student_id,
+                status,
+                CASE WHEN status = 'waiting'
+                    THEN count(*) FILTER (WHERE status = 'waiting') OVER (ORDER BY created_at, id)
+                END as position,
+                offered_at,
+                hold_expires_at,
+                created_at,
+                updated_at
+            FROM waitlist_entries WHERE course_id = $1 AND status in ('waiting', 'offered')
+        ) entries WHERE student_id = $2"#,
+        course_id,
+        student_id,
+    ).fetch_one(&mut **tx).await?)
+}
+
+pub async fn get_capacity(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid) -> Result<CourseCapacity, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let capacity = lock_capacity(&mut tx, course_id).await?
+        .ok_or(EzyTutorError::NotFound("No capacity set for this course".to_string()))?;
+    let capacity = settle_waitlist(&mut tx, course_id, capacity).await?;
+    tx.commit().await?;
+
+    Ok(capacity)
+}
+
+pub async fn set_capacity(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, capacity: i32) -> Result<CourseCapacity, EzyTutorError> {
+    if capacity < 0 {
+        return Err(EzyTutorError::InvalidInput("Capacity can not be negative".to_string()));
+    }
+
+    let mut tx = db.begin().await?;
+    let register_time = Utc::now().naive_utc();
+    sqlx::query!(
+        r#"INSERT INTO
+            course_seats (
+                course_id,
+                capacity,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (course_id) DO UPDATE
+            SET capacity = EXCLUDED.capacity,
+                updated_at = EXCLUDED.updated_at"#,
+        course_id,
+        capacity,
+        &register_time,
+        &register_time,
+    ).execute(&mut *tx).await?;
+    lock_capacity(&mut tx, course_id).await?;
+    let capacity = settle_waitlist(&mut tx, course_id, capacity).await?;
+    tx.commit().await?;
+
+    Ok(capacity)
+}
+
+/// Enrolls a student, taking a free seat or accepting the seat held for them by a waitlist offer.
+pub async fn enroll(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, student_id: Uuid) -> Result<Enrollment, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let now = Utc::now().naive_utc();
+
+    if let Some(capacity) = lock_capacity(&mut tx, course_id).await? {
+        let capacity = settle_waitlist(&mut tx, course_id, capacity).await?;
+        let accepted_offer = sqlx::query!(
+            r#"UPDATE waitlist_entries
+                SET status = 'accepted',
+                    updated_at = $3
+            WHERE course_id = $1 AND student_id = $2 AND status = 'offered'"#,
+            course_id,
+            student_id,
+            now,
+        ).execute(&mut *tx).await?.rows_affected() > 0;
+
+        if !accepted_offer {
+            let waiting = sqlx::query_scalar!(
+                r#"SELECT count(*) as "count!" FROM waitlist_entries WHERE course_id = $1 AND status = 'waiting'"#,
+                course_id
+            ).fetch_one(&mut *tx).await?;
+            if capacity.available == 0 || waiting > 0 {
+                return Err(EzyTutorError::Conflict("Course is full, join the waitlist instead".to_string()));
+            }
+        }
+    }
+
+    let enrollment = sqlx::query_as!(Enrollment,
+        r#"INSERT INTO
+            enrollments (
+                course_id,
+                student_id,
+                status,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, 'enrolled', $3, $3)
+        ON CONFLICT (course_id, student_id) DO UPDATE
+            SET status = 'enrolled',
+                updated_at = EXCLUDED.updated_at
+            WHERE enrollments.status <> 'enrolled'
+        returning
+                course_id,
+                student_id,
+                status,
+                created_at,
+                updated_at"#,
+        course_id,
+        student_id,
+        now,
+    ).fetch_optional(&mut *tx).await?
+        .ok_or(EzyTutorError::Conflict("Student is already enrolled".to_string()))?;
+    tx.commit().await?;
+
+    Ok(enrollment)
+}
+
+/// Withdraws a student and offers the freed seat to the next student on the waitlist.
+pub async fn withdraw(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, student_id: Uuid) -> Result<Enrollment, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let capacity = lock_capacity(&mut tx, course_id).await?;
+
+    let enrollment = sqlx::query_as!(Enrollment,
+        r#"UPDATE enrollments
+            SET status = 'withdrawn',
+                updated_at = $3
+        WHERE course_id = $1 AND student_id = $2 AND status = 'enrolled'
+        returning
+                course_id,
+                student_id,
+                status,
+                created_at,
+                updated_at"#,
+        course_id,
+        student_id,
+        Utc::now().naive_utc(),
+    ).fetch_one(&mut *tx).await?;
+
+    if let Some(capacity) = capacity {
+        settle_waitlist(&mut tx, course_id, capacity).await?;
+    }
+    tx.commit().await?;
+
+    Ok(enrollment)
+}
+
+pub async fn join_waitlist(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, student_id: Uuid) -> Result<WaitlistEntry, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let capacity = lock_capacity(&mut tx, course_id).await?
+        .ok_or(EzyTutorError::Conflict("Course has no seat limit, enroll instead".to_string()))?;
+    let capacity = settle_waitlist(&mut tx, course_id, capacity).await?;
+
+    let enrolled = sqlx::query_scalar!(
+        r#"SELECT count(*) as "count!" FROM enrollments WHERE course_id = $1 AND student_id = $2 AND status = 'enrolled'"#,
+        course_id,
+        student_id,
+    ).fetch_one(&mut *tx).await?;
+    if enrolled > 0 {
+        return Err(EzyTutorError::Conflict("Student is already enrolled".to_string()));
+    }
+    if capacity.available > 0 {
+        return Err(EzyTutorError::Conflict("Course has seats available, enroll instead".to_string()));
+    }
+
+    let now = Utc::now().naive_utc();
+    sqlx::query!(
+        r#"INSERT INTO
+            waitlist_entries (
+                id,
+                course_id,
+                student_id,
+                status,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, 'waiting', $4, $4)"#,
+        Uuid::new_v4(),
+        course_id,
+        student_id,
+        now,
+    ).execute(&mut *tx).await?;
+    let entry = waitlist_entry(&mut tx, course_id, student_id).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+pub async fn leave_waitlist(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, student_id: Uuid) -> Result<WaitlistEntry, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let capacity = lock_capacity(&mut tx, course_id).await?;
+    let entry = waitlist_entry(&mut tx, course_id, student_id).await?;
+
+    sqlx::query!(
+        r#"UPDATE waitlist_entries
+            SET status = 'left',
+                updated_at = $2
+        WHERE id = $1"#,
+        entry.id,
+        Utc::now().naive_utc(),
+    ).execute(&mut *tx).await?;
+
+    if let Some(capacity) = capacity {
+        settle_waitlist(&mut tx, course_id, capacity).await?;
+    }
+    tx.commit().await?;
+
+    Ok(WaitlistEntry { status: "left".to_string(), position: None, ..entry })
+}
+
+pub async fn get_waitlist(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid) -> Result<Vec<WaitlistEntry>, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    if let Some(capacity) = lock_capacity(&mut tx, course_id).await? {
+        settle_waitlist(&mut tx, course_id, capacity).await?;
+    }
+
+    let entries = sqlx::query_as!(WaitlistEntry,
+        r#"SELECT id,
+                course_id,
+                student_id,
+                status,
+                CASE WHEN status = 'waiting'
+                    THEN count(*) FILTER (WHERE status = 'waiting') OVER (ORDER BY created_at, id)
+                END as position,
+                offered_at,
+                hold_expires_at,
+                created_at,
+                updated_at
+            FROM waitlist_entries WHERE course_id = $1 AND status in ('waiting', 'offered')
+            ORDER BY created_at, id"#,
+        course_id,
+    ).fetch_all(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(entries)
+}
+
+pub async fn get_waitlist_entry(db: impl Acquire<'_, Database = Postgres>, course_id: Uuid, student_id: Uuid) -> Result<WaitlistEntry, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    if let Some(capacity) = lock_capacity(&mut tx, course_id).await? {
+        settle_waitlist(&mut tx, course_id, capacity).await?;
+    }
+    let entry = waitlist_entry(&mut tx, course_id, student_id).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
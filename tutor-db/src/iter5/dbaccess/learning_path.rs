@@ -0,0 +1,149 @@
+use sqlx::{Acquire, PgExecutor, Postgres, Transaction};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::learning_path::{LearningPath, LearningPathCourse, LearningPathDto};
+use chrono::Utc;
+
+pub async fn get_paths(db: impl PgExecutor<'_>) -> Result<Vec<LearningPath>, EzyTutorError> {
+    Ok(sqlx::query_as!(LearningPath,
+        r#"SELECT id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at,
+                deleted_at
+            FROM learning_paths WHERE deleted_at is null
+            ORDER BY created_at, id"#
+    ).fetch_all(db).await?)
+}
+
+pub async fn by_id(db: impl PgExecutor<'_>, path_id: Uuid) -> Result<LearningPath, EzyTutorError> {
+    Ok(sqlx::query_as!(LearningPath,
+        r#"SELECT id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at,
+                deleted_at
+            FROM learning_paths WHERE id = $1 AND deleted_at is null"#,
+        path_id
+    ).fetch_one(db).await?)
+}
+
+/// Memberships of the given paths whose course is not soft-deleted, in path order.
+pub async fn get_active_path_courses(db: impl PgExecutor<'_>, path_ids: &[Uuid]) -> Result<Vec<LearningPathCourse>, EzyTutorError> {
+    Ok(sqlx::query_as!(LearningPathCourse,
+        r#"SELECT lpc.path_id,
+                lpc.course_id,
+                lpc.position
+            FROM learning_path_courses lpc
+            JOIN ezy_course_c4 c ON c.id = lpc.course_id
+            WHERE lpc.path_id = ANY($1) AND c.deleted_at is null
+            ORDER BY lpc.path_id, lpc.position"#,
+        path_ids
+    ).fetch_all(db).await?)
+}
+
+async fn replace_path_courses(tx: &mut Transaction<'_, Postgres>, path_id: Uuid, course_ids: &[Uuid]) -> Result<(), EzyTutorError> {
+    sqlx::query!(
+        r#"DELETE FROM learning_path_courses WHERE path_id = $1"#,
+        path_id
+    ).execute(&mut **tx).await?;
+
+    let inserted = sqlx::query!(
+        r#"INSERT INTO learning_path_courses (path_id, course_id, position)
+            SELECT $1, members.course_id, members.position
+            FROM UNNEST($2::uuid[]) WITH ORDINALITY AS members(course_id, position)
+            JOIN ezy_course_c4 c ON c.id = members.course_id AND c.deleted_at is null"#,
+        path_id,
+        course_ids,
+    ).execute(&mut **tx).await?.rows_affected();
+
+    if inserted != course_ids.len() as u64 {
+        return Err(EzyTutorError::InvalidInput("Learning paths can only contain existing courses".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn create(db: impl Acquire<'_, Database = Postgres>, dto: LearningPathDto) -> Result<LearningPath, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let register_time = Utc::now().naive_utc();
+    let path = sqlx::query_as!(LearningPath,
+        r#"INSERT INTO
+            learning_paths (
+                id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at
+        ) VALUES ($1, $2, $3, $4, $5, $6)
+        returning
+                id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at,
+                deleted_at"#,
+        Uuid::new_v4(),
+        dto.title,
+        dto.description,
+        dto.price,
+        &register_time,
+        &register_time,
+    ).fetch_one(&mut *tx).await?;
+    replace_path_courses(&mut tx, path.id, &dto.course_ids).await?;
+    tx.commit().await?;
+
+    Ok(path)
+}
+
+pub async fn update(db: impl Acquire<'_, Database = Postgres>, path_id: Uuid, dto: LearningPathDto) -> Result<LearningPath, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let path = sqlx::query_as!(LearningPath,
+        r#"UPDATE learning_paths
+            SET title = $1,
+                description = $2,
+                price = $3,
+                updated_at = $4
+        WHERE id = $5 AND deleted_at is null
+        returning
+                id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at,
+                deleted_at"#,
+        dto.title,
+        dto.description,
+        dto.price,
+        Utc::now().naive_utc(),
+        path_id,
+    ).fetch_one(&mut *tx).await?;
+    replace_path_courses(&mut tx, path.id, &dto.course_ids).await?;
+    tx.commit().await?;
+
+    Ok(path)
+}
+
+pub async fn soft_delete(db: impl PgExecutor<'_>, path_id: Uuid) -> Result<LearningPath, EzyTutorError> {
+    Ok(sqlx::query_as!(LearningPath,
+        r#"UPDATE learning_paths
+            SET deleted_at = $1
+        WHERE id = $2 AND deleted_at is null
+        returning
+                id,
+                title,
+                description,
+                price,
+                created_at,
+                updated_at,
+                deleted_at"#,
+        Utc::now().naive_utc(),
+        path_id,
+    ).fetch_one(db).await?)
+}
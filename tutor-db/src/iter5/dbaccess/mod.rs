@@ -1,2 +1,13 @@
+pub mod booking;
+pub mod coupon;
 pub mod course;
+pub mod course_tag;
+pub mod course_translation;
+pub mod course_tutor;
+pub mod credential;
+pub mod duplicate;
+pub mod enrollment;
+pub mod learning_path;
+pub mod slug;
+pub mod stats;
 pub mod tutor;
\ No newline at end of file
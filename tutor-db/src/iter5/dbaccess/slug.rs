@@ -0,0 +1,74 @@
+use sqlx::{PgExecutor, Postgres, Transaction};
+use crate::errors::EzyTutorError;
+use crate::models::slug::{SlugOwner, SlugRecord};
+use crate::slug::slugify;
+
+const MAX_BASE_SLUG_LENGTH: usize = 140;
+
+/// Picks the slug for `name`: the plain slug when it is free, otherwise the first free `-2`,
+/// `-3`... suffix. Slugs once given to another tutor or course are never reused, so their old
+/// links keep redirecting to the right resource.
+pub async fn available_slug(tx: &mut Transaction<'_, Postgres>, owner: SlugOwner, name: &str) -> Result<String, EzyTutorError> {
+    let base = base_slug(owner, name);
+    let taken = sqlx::query_as!(SlugRecord,
+        r#"SELECT slug,
+                tutor_id,
+                course_id,
+                created_at
+            FROM slugs WHERE slug = $1 OR slug LIKE $1 || '-%'"#,
+        base,
+    ).fetch_all(&mut **tx).await?;
+
+    for candidate in candidates(base) {
+        match taken.iter().find(|record| record.slug == candidate) {
+            None => return Ok(candidate),
+            Some(record) if record.owner() == Some(owner) => return Ok(candidate),
+            Some(_) => {}
+        }
+    }
+    unreachable!("the candidate slugs are unbounded")
+}
+
+/// The slug of `name` without any suffix, `tutor` or `course` when nothing is left of the name.
+pub fn base_slug(owner: SlugOwner, name: &str) -> String {
+    let mut base = slugify(name);
+    base.truncate(MAX_BASE_SLUG_LENGTH);
+    match (base.trim_end_matches('-'), owner) {
+        ("", SlugOwner::Tutor(_)) => "tutor".to_string(),
+        ("", SlugOwner::Course(_)) => "course".to_string(),
+        (base, _) => base.to_string(),
+    }
+}
+
+/// The base slug followed by its `-2`, `-3`... variants, in the order they are tried.
+pub fn candidates(base: String) -> impl Iterator<Item = String> {
+    std::iter::once(base.clone()).chain((2..).map(move |n| format!("{}-{}", base, n)))
+}
+
+pub async fn record_slug(tx: &mut Transaction<'_, Postgres>, owner: SlugOwner, slug: &str) -> Result<(), EzyTutorError> {
+    let (tutor_id, course_id) = match owner {
+        SlugOwner::Tutor(tutor_id) => (Some(tutor_id), None),
+        SlugOwner::Course(course_id) => (None, Some(course_id)),
+    };
+    sqlx::query!(
+        r#"INSERT INTO slugs (slug, tutor_id, course_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (slug) DO NOTHING"#,
+        slug,
+        tutor_id,
+        course_id,
+    ).execute(&mut **tx).await?;
+
+    Ok(())
+}
+
+pub async fn find(db: impl PgExecutor<'_>, slug: &str) -> Result<SlugRecord, EzyTutorError> {
+    Ok(sqlx::query_as!(SlugRecord,
+        r#"SELECT slug,
+                tutor_id,
+                course_id,
+                created_at
+            FROM slugs WHERE slug = $1"#,
+        slug,
+    ).fetch_one(db).await?)
+}
@@ -0,0 +1,72 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::stats::{ActivityBucket, StatsBucket, TutorStats};
+
+pub async fn get_tutor_stats(db: impl PgExecutor<'_>, tutor_id: Uuid) -> Result<TutorStats, EzyTutorError> {
+    let summary = sqlx::query!(
+        r#"WITH taught AS (
+            SELECT c.id, c.price, c.deleted_at
+            FROM ezy_course_c4 c
+            JOIN course_tutors ct ON ct.course_id = c.id AND ct.tutor_id = $1
+        )
+        SELECT
+            (SELECT count(*) FROM taught WHERE deleted_at is null) as "active_courses!",
+            (SELECT count(*) FROM taught WHERE deleted_at is not null) as "deleted_courses!",
+            (SELECT avg(price)::float8 FROM taught WHERE deleted_at is null) as average_price,
+            (SELECT min(price) FROM taught WHERE deleted_at is null) as min_price,
+            (SELECT max(price) FROM taught WHERE deleted_at is null) as max_price,
+            (SELECT count(*) FROM enrollments e JOIN taught t ON t.id = e.course_id
+                WHERE e.status = 'enrolled') as "total_enrollments!",
+            (SELECT avg(rating)::float8 FROM tutor_ratings WHERE tutor_id = $1) as average_rating,
+            (SELECT count(*) FROM tutor_ratings WHERE tutor_id = $1) as "ratings!""#,
+        tutor_id
+    ).fetch_one(db).await?;
+
+    Ok(TutorStats {
+        tutor_id,
+        active_courses: summary.active_courses,
+        deleted_courses: summary.deleted_courses,
+        average_price: summary.average_price,
+        min_price: summary.min_price,
+        max_price: summary.max_price,
+        total_enrollments: summary.total_enrollments,
+        average_rating: summary.average_rating,
+        ratings: summary.ratings,
+        activity: vec![],
+    })
+}
+
+/// Courses created and enrollments made in each of the last `buckets` weeks or months, empty
+/// buckets included. Buckets are in UTC like `created_at`, whatever the session time zone.
+pub async fn get_tutor_activity(db: impl PgExecutor<'_>, tutor_id: Uuid, bucket: StatsBucket, buckets: i32) -> Result<Vec<ActivityBucket>, EzyTutorError> {
+    Ok(sqlx::query_as!(ActivityBucket,
+        r#"WITH buckets AS (
+            SELECT generate_series(
+                date_trunc($2, (now() AT TIME ZONE 'utc')) - ($3 - 1) * ('1 ' || $2)::interval,
+                date_trunc($2, (now() AT TIME ZONE 'utc')),
+                ('1 ' || $2)::interval) AS starts_at
+        ), taught AS (
+            SELECT c.id, c.created_at
+            FROM ezy_course_c4 c
+            JOIN course_tutors ct ON ct.course_id = c.id AND ct.tutor_id = $1
+        ), created AS (
+            SELECT date_trunc($2, created_at) AS starts_at, count(*) AS courses
+            FROM taught GROUP BY 1
+        ), enrolled AS (
+            SELECT date_trunc($2, e.created_at) AS starts_at, count(*) AS enrollments
+            FROM enrollments e JOIN taught t ON t.id = e.course_id
+            GROUP BY 1
+        )
+        SELECT b.starts_at::date as "starts_on!",
+            coalesce(c.courses, 0) as "courses_created!",
+            coalesce(e.enrollments, 0) as "enrollments!"
+        FROM buckets b
+        LEFT JOIN created c ON c.starts_at = b.starts_at
+        LEFT JOIN enrolled e ON e.starts_at = b.starts_at
+        ORDER BY b.starts_at"#,
+        tutor_id,
+        bucket.unit(),
+        buckets,
+    ).fetch_all(db).await?)
+}
@@ -1,10 +1,160 @@
-use sqlx::PgPool;
+use sqlx::{Acquire, PgExecutor, Postgres};
 use uuid::Uuid;
 use crate::errors::EzyTutorError;
-use crate::models::tutor::{CreateTutorDto, Tutor};
-use chrono::Utc;
+use crate::dbaccess::slug;
+use crate::markdown;
+use crate::models::slug::SlugOwner;
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor, TutorCursor, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+use chrono::{NaiveDateTime, Utc};
 
-pub async fn get_tutors(pool: &PgPool) -> Result<Vec<Tutor>, EzyTutorError> {
+/// A listed tutor with the average rating it was sorted by.
+struct RatedTutor {
+    id: Uuid,
+    name: String,
+    pic_url: String,
+    profile: String,
+    created_at: NaiveDateTime,
+    updated_at: Option<NaiveDateTime>,
+    deleted_at: Option<NaiveDateTime>,
+    slug: String,
+    profile_html: String,
+    verified: bool,
+    version: i32,
+    rating: f64,
+}
+
+impl RatedTutor {
+    fn listed(self) -> (Tutor, TutorCursor) {
+        let rating = self.rating;
+        let tutor = Tutor {
+            id: self.id,
+            name: self.name,
+            pic_url: self.pic_url,
+            profile: self.profile,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            deleted_at: self.deleted_at,
+            slug: self.slug,
+            profile_html: self.profile_html,
+            verified: self.verified,
+            version: self.version,
+        };
+        let cursor = TutorCursor::of(TutorSort::Rating, &tutor, rating);
+        (tutor, cursor)
+    }
+}
+
+/// One page of active tutors, `limit` at most, whose name starts with `name_prefix` and whose
+/// badge matches `verified` when given, each with its cursor.
+/// Pages are keyset based: `after` holds the sort key and id of the last tutor of the previous
+/// page as they were then, and the next page starts right after them, with `id` breaking ties.
+pub async fn get_tutors(db: impl PgExecutor<'_>, sort: TutorSort, name_prefix: Option<&str>, verified: Option<bool>, after: Option<&TutorCursor>, limit: i64) -> Result<Vec<(Tutor, TutorCursor)>, EzyTutorError> {
+    let after_id = after.map(TutorCursor::id);
+    let tutors = match sort {
+        TutorSort::Created => sqlx::query_as!(Tutor, r#"
+            SELECT  id,
+                    name,
+                    pic_url,
+                    profile,
+                    created_at,
+                    updated_at,
+                    deleted_at,
+                    slug,
+                    profile_html,
+                    verified,
+                    version
+            FROM tutors
+            WHERE deleted_at is null
+                AND ($1::text is null OR starts_with(lower(name), lower($1)))
+                AND ($4::bool is null OR verified = $4)
+                AND ($2::timestamp is null OR (created_at, id) > ($2, $5))
+            ORDER BY created_at, id
+            LIMIT $3
+        "#,
+        name_prefix,
+        match after { Some(TutorCursor::Created { created_at, .. }) => Some(*created_at), _ => None },
+        limit,
+        verified,
+        after_id,
+        ).fetch_all(db).await?
+            .into_iter()
+            .map(|tutor| {
+                let cursor = TutorCursor::of(sort, &tutor, 0.0);
+                (tutor, cursor)
+            })
+            .collect(),
+        TutorSort::Name => sqlx::query_as!(Tutor, r#"
+            SELECT  id,
+                    name,
+                    pic_url,
+                    profile,
+                    created_at,
+                    updated_at,
+                    deleted_at,
+                    slug,
+                    profile_html,
+                    verified,
+                    version
+            FROM tutors
+            WHERE deleted_at is null
+                AND ($1::text is null OR starts_with(lower(name), lower($1)))
+                AND ($4::bool is null OR verified = $4)
+                AND ($2::text is null OR (lower(name), id) > (lower($2), $5))
+            ORDER BY lower(name), id
+            LIMIT $3
+        "#,
+        name_prefix,
+        match after { Some(TutorCursor::Name { name, .. }) => Some(name.as_str()), _ => None },
+        limit,
+        verified,
+        after_id,
+        ).fetch_all(db).await?
+            .into_iter()
+            .map(|tutor| {
+                let cursor = TutorCursor::of(sort, &tutor, 0.0);
+                (tutor, cursor)
+            })
+            .collect(),
+        TutorSort::Rating => sqlx::query_as!(RatedTutor, r#"
+            WITH rated AS (
+                SELECT t.*, coalesce((SELECT avg(r.rating) FROM tutor_ratings r WHERE r.tutor_id = t.id), 0)::float8 as rating
+                FROM tutors t
+            )
+            SELECT  id as "id!",
+                    name as "name!",
+                    pic_url as "pic_url!",
+                    profile as "profile!",
+                    created_at as "created_at!",
+                    updated_at,
+                    deleted_at,
+                    slug as "slug!",
+                    profile_html as "profile_html!",
+                    verified as "verified!",
+                    version as "version!",
+                    rating as "rating!"
+            FROM rated
+            WHERE deleted_at is null
+                AND ($1::text is null OR starts_with(lower(name), lower($1)))
+                AND ($4::bool is null OR verified = $4)
+                AND ($2::float8 is null OR rating < $2 OR (rating = $2 AND id > $5))
+            ORDER BY rating DESC, id
+            LIMIT $3
+        "#,
+        name_prefix,
+        match after { Some(TutorCursor::Rating { rating, .. }) => Some(*rating), _ => None },
+        limit,
+        verified,
+        after_id,
+        ).fetch_all(db).await?
+            .into_iter()
+            .map(RatedTutor::listed)
+            .collect(),
+    };
+    Ok(tutors)
+}
+
+pub async fn by_id(db: impl PgExecutor<'_>, id: Uuid) -> Result<Tutor, EzyTutorError> {
     Ok(sqlx::query_as!(Tutor, r#"
         SELECT  id,
                 name,
@@ -12,12 +162,18 @@ pub async fn get_tutors(pool: &PgPool) -> Result<Vec<Tutor>, EzyTutorError> {
                 profile,
                 created_at,
                 updated_at,
-                deleted_at
-        FROM tutors WHERE deleted_at is null
-    "#).fetch_all(pool).await?)
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        FROM tutors WHERE id = $1 AND deleted_at is null
+    "#,
+    id,
+    ).fetch_one(db).await?)
 }
 
-pub async fn by_id(pool: &PgPool, id: Uuid) -> Result<Tutor, EzyTutorError> {
+pub async fn get_tutors_by_ids(db: impl PgExecutor<'_>, ids: &[Uuid]) -> Result<Vec<Tutor>, EzyTutorError> {
     Ok(sqlx::query_as!(Tutor, r#"
         SELECT  id,
                 name,
@@ -25,15 +181,22 @@ pub async fn by_id(pool: &PgPool, id: Uuid) -> Result<Tutor, EzyTutorError> {
                 profile,
                 created_at,
                 updated_at,
-                deleted_at
-        FROM tutors WHERE id = $1 AND deleted_at is null
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        FROM tutors WHERE id = ANY($1) AND deleted_at is null
     "#,
-    id,
-    ).fetch_one(pool).await?)
+    ids,
+    ).fetch_all(db).await?)
 }
 
-pub async fn create(pool: &PgPool, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError> {
-    sqlx::query_as!(Tutor, r#"
+pub async fn create(db: impl Acquire<'_, Database = Postgres>, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let id = Uuid::new_v4();
+    let slug = slug::available_slug(&mut tx, SlugOwner::Tutor(id), &dto.name).await?;
+    let tutor = sqlx::query_as!(Tutor, r#"
         INSERT INTO
             tutors (
                 id,
@@ -41,8 +204,10 @@ pub async fn create(pool: &PgPool, dto: CreateTutorDto) -> Result<Tutor, EzyTuto
                 pic_url,
                 profile,
                 created_at,
-                updated_at
-        ) VALUES ($1, $2, $3, $4, $5, $6)
+                updated_at,
+                slug,
+                profile_html
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING
                 id,
                 name,
@@ -50,25 +215,63 @@ pub async fn create(pool: &PgPool, dto: CreateTutorDto) -> Result<Tutor, EzyTuto
                 profile,
                 created_at,
                 updated_at,
-                deleted_at
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
         "#,
-        Uuid::new_v4(),
+        id,
         dto.name,
         dto.pic_url,
         dto.profile,
         Utc::now().naive_utc(),
         Utc::now().naive_utc(),
-    ).fetch_one(pool).await.map_err(|err| err.into())
+        slug,
+        markdown::to_html(&dto.profile),
+    ).fetch_one(&mut *tx).await?;
+    slug::record_slug(&mut tx, SlugOwner::Tutor(id), &tutor.slug).await?;
+    tx.commit().await?;
+
+    Ok(tutor)
 }
 
-pub async fn update(pool: &PgPool, update_data: CreateTutorDto, id: Uuid) -> Result<Tutor, EzyTutorError> {
-    Ok(sqlx::query_as!(Tutor, r#"
+/// Locks the tutor and builds its new state from the current one with `changes`, so that
+/// nothing can be written in between.
+pub async fn update_with<F>(db: impl Acquire<'_, Database = Postgres>, id: Uuid, changes: F) -> Result<Tutor, EzyTutorError>
+    where F: FnOnce(&Tutor) -> Result<CreateTutorDto, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let current = sqlx::query_as!(Tutor, r#"
+        SELECT  id,
+                name,
+                pic_url,
+                profile,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        FROM tutors WHERE id = $1 AND deleted_at is null FOR UPDATE
+        "#,
+        id,
+    ).fetch_one(&mut *tx).await?;
+    let update_data = changes(&current)?;
+    let slug = if current.name == update_data.name {
+        current.slug
+    } else {
+        slug::available_slug(&mut tx, SlugOwner::Tutor(id), &update_data.name).await?
+    };
+
+    let tutor = sqlx::query_as!(Tutor, r#"
         UPDATE tutors
           SET   name = $1,
                 pic_url = $2,
                 profile = $3,
-                updated_at = $4
-        WHERE id = $5 AND deleted_at is null
+                slug = $4,
+                profile_html = $5
+        WHERE id = $6 AND deleted_at is null
         RETURNING
                 id,
                 name,
@@ -76,18 +279,86 @@ pub async fn update(pool: &PgPool, update_data: CreateTutorDto, id: Uuid) -> Res
                 profile,
                 created_at,
                 updated_at,
-                deleted_at
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
         "#,
         update_data.name,
         update_data.pic_url,
         update_data.profile,
-        Utc::now().naive_utc(),
+        slug,
+        markdown::to_html(&update_data.profile),
         id,
-    ).fetch_one(pool).await?)
+    ).fetch_one(&mut *tx).await?;
+    slug::record_slug(&mut tx, SlugOwner::Tutor(id), &tutor.slug).await?;
+    tx.commit().await?;
+
+    Ok(tutor)
 }
 
-pub async fn soft_delete(pool: &PgPool, id: Uuid) -> Result<Tutor, EzyTutorError> {
-    sqlx::query_as!(Tutor, r#"
+/// Applies a merge patch in one transaction: the row is locked before the slug is worked out,
+/// and only the members present in the patch are written.
+pub async fn patch(db: impl Acquire<'_, Database = Postgres>, patch: PatchTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let current = sqlx::query!(r#"
+        SELECT name, slug, version FROM tutors WHERE id = $1 AND deleted_at is null FOR UPDATE
+        "#,
+        id,
+    ).fetch_one(&mut *tx).await?;
+    expected.check(current.version)?;
+    let name = patch.name.flatten();
+    let profile = patch.profile.flatten();
+    let slug = match &name {
+        Some(name) if *name != current.name => slug::available_slug(&mut tx, SlugOwner::Tutor(id), name).await?,
+        _ => current.slug,
+    };
+
+    let tutor = sqlx::query_as!(Tutor, r#"
+        UPDATE tutors
+          SET   name = coalesce($1, name),
+                pic_url = coalesce($2, pic_url),
+                profile = coalesce($3, profile),
+                slug = $4,
+                profile_html = coalesce($5, profile_html)
+        WHERE id = $6 AND deleted_at is null
+        RETURNING
+                id,
+                name,
+                pic_url,
+                profile,
+                created_at,
+                updated_at,
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
+        "#,
+        name,
+        patch.pic_url.flatten(),
+        profile,
+        slug,
+        profile.as_deref().map(markdown::to_html),
+        id,
+    ).fetch_one(&mut *tx).await?;
+    slug::record_slug(&mut tx, SlugOwner::Tutor(id), &tutor.slug).await?;
+    tx.commit().await?;
+
+    Ok(tutor)
+}
+
+pub async fn soft_delete(db: impl Acquire<'_, Database = Postgres>, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let version = sqlx::query_scalar!(r#"
+        SELECT version FROM tutors WHERE id = $1 AND deleted_at is null FOR UPDATE
+        "#,
+        id,
+    ).fetch_one(&mut *tx).await?;
+    expected.check(version)?;
+
+    let tutor = sqlx::query_as!(Tutor, r#"
         UPDATE tutors
           SET  deleted_at = $1
         WHERE id = $2 AND deleted_at is null
@@ -98,9 +369,54 @@ pub async fn soft_delete(pool: &PgPool, id: Uuid) -> Result<Tutor, EzyTutorError
                 profile,
                 created_at,
                 updated_at,
-                deleted_at
+                deleted_at,
+                slug,
+                profile_html,
+                verified,
+                version
         "#,
         Utc::now().naive_utc(),
         id,
-    ).fetch_one(pool).await.map_err(|err| err.into())
-}
\ No newline at end of file
+    ).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(tutor)
+}
+
+pub async fn rate(db: impl PgExecutor<'_>, tutor_id: Uuid, student_id: Uuid, rating: i16) -> Result<TutorRating, EzyTutorError> {
+    Ok(sqlx::query_as!(TutorRating, r#"
+        INSERT INTO tutor_ratings (tutor_id, student_id, rating)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (tutor_id, student_id)
+            DO UPDATE SET rating = excluded.rating, updated_at = now()
+        RETURNING
+                tutor_id,
+                student_id,
+                rating,
+                created_at,
+                updated_at
+        "#,
+        tutor_id,
+        student_id,
+        rating,
+    ).fetch_one(db).await?)
+}
+
+/// Renders `profile_html` from `profile` again for the tutors where it does not match, such as
+/// rows loaded by `dbscripts/seed.sql`. Returns how many were rendered.
+pub async fn render_profiles(db: impl Acquire<'_, Database = Postgres>) -> Result<u64, EzyTutorError> {
+    let mut tx = db.begin().await?;
+    let tutors = sqlx::query!("SELECT id, profile, profile_html FROM tutors FOR UPDATE")
+        .fetch_all(&mut *tx).await?;
+    let mut rendered = 0;
+    for tutor in tutors {
+        let profile_html = markdown::to_html(&tutor.profile);
+        if profile_html != tutor.profile_html {
+            sqlx::query!("UPDATE tutors SET profile_html = $1 WHERE id = $2", profile_html, tutor.id)
+                .execute(&mut *tx).await?;
+            rendered += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(rendered)
+}
@@ -0,0 +1,380 @@
+-- Brings a database created by the old dbscripts (tutor-course.sql) to the schema of the initial
+-- migration, run by the migrate baseline subcommand before it records that migration as applied.
+-- Every statement is a no-op for what already exists. The slugs are added empty and filled in by
+-- the subcommand, which then makes them required and unique and renders the HTML columns.
+
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+alter table tutors
+    add column if not exists slug         varchar(160),
+    -- profile is Markdown, this is its sanitized HTML rendering
+    add column if not exists profile_html text    NOT NULL default '',
+    -- kept in sync with the approved rows of tutor_credentials
+    add column if not exists verified     boolean NOT NULL default false;
+
+-- Keyset pagination of the tutor listing, one index per sort order.
+create index if not exists tutors_created_at_id on tutors (created_at, id) where deleted_at is null;
+create index if not exists tutors_lower_name_id on tutors (lower(name), id) where deleted_at is null;
+-- Fuzzy duplicate detection on the tutor name (pg_trgm).
+create index if not exists tutors_lower_name_trgm on tutors using gin (lower(name) gin_trgm_ops) where deleted_at is null;
+
+create table if not exists ezy_course_c4
+(
+    id          uuid UNIQUE  NOT NULL,
+    tutor_id    uuid         NOT NULL,
+    name        varchar(140) NOT NULL,
+    description text,
+    format      varchar(30),
+    structure   varchar(30),
+    duration    varchar(30),
+    price       INT,
+    language    varchar(30),
+    level       varchar(30),
+    posted_time timestamp    NOT NULL default now(),
+    created_at  timestamp    NOT NULL default now(),
+    updated_at  timestamp    NOT NULL default now(),
+    deleted_at  timestamp,
+    PRIMARY KEY (id, created_at, updated_at),
+    UNIQUE (id, tutor_id, created_at, updated_at),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+alter table ezy_course_c4
+    add column if not exists slug             varchar(160),
+    -- description is Markdown, this is its sanitized HTML rendering
+    add column if not exists description_html text;
+
+create table if not exists course_translations
+(
+    course_id   uuid         NOT NULL,
+    locale      varchar(35)  NOT NULL,
+    name        varchar(140) NOT NULL,
+    description text,
+    created_at  timestamp    NOT NULL default now(),
+    updated_at  timestamp    NOT NULL default now(),
+    PRIMARY KEY (course_id, locale),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists course_seats
+(
+    course_id  uuid      NOT NULL PRIMARY KEY,
+    capacity   INT       NOT NULL CHECK (capacity >= 0),
+    created_at timestamp NOT NULL default now(),
+    updated_at timestamp NOT NULL default now(),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists enrollments
+(
+    course_id  uuid        NOT NULL,
+    student_id uuid        NOT NULL,
+    status     varchar(20) NOT NULL default 'enrolled' CHECK (status in ('enrolled', 'withdrawn')),
+    created_at timestamp   NOT NULL default now(),
+    updated_at timestamp   NOT NULL default now(),
+    PRIMARY KEY (course_id, student_id),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists waitlist_entries
+(
+    id              uuid        NOT NULL PRIMARY KEY,
+    course_id       uuid        NOT NULL,
+    student_id      uuid        NOT NULL,
+    status          varchar(20) NOT NULL default 'waiting'
+        CHECK (status in ('waiting', 'offered', 'accepted', 'expired', 'left')),
+    offered_at      timestamp,
+    hold_expires_at timestamp,
+    created_at      timestamp   NOT NULL default now(),
+    updated_at      timestamp   NOT NULL default now(),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create unique index if not exists waitlist_entries_active_student
+    on waitlist_entries (course_id, student_id) where status in ('waiting', 'offered');
+
+create table if not exists coupons
+(
+    id              uuid        NOT NULL PRIMARY KEY,
+    tutor_id        uuid        NOT NULL,
+    code            varchar(40) NOT NULL,
+    discount_type   varchar(20) NOT NULL CHECK (discount_type in ('percentage', 'fixed')),
+    amount          INT         NOT NULL CHECK (amount > 0),
+    expires_at      timestamp,
+    max_redemptions INT CHECK (max_redemptions > 0),
+    redemptions     INT         NOT NULL default 0,
+    created_at      timestamp   NOT NULL default now(),
+    updated_at      timestamp   NOT NULL default now(),
+    deleted_at      timestamp,
+    CHECK (max_redemptions is null OR redemptions <= max_redemptions),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create unique index if not exists coupons_tutor_code on coupons (tutor_id, code) where deleted_at is null;
+
+create table if not exists coupon_courses
+(
+    coupon_id uuid NOT NULL,
+    course_id uuid NOT NULL,
+    PRIMARY KEY (coupon_id, course_id),
+    CONSTRAINT fk_coupon
+        FOREIGN KEY (coupon_id)
+            REFERENCES coupons (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists coupon_redemptions
+(
+    id               uuid      NOT NULL PRIMARY KEY,
+    coupon_id        uuid      NOT NULL,
+    course_id        uuid      NOT NULL,
+    student_id       uuid      NOT NULL,
+    price            INT       NOT NULL,
+    effective_price  INT       NOT NULL,
+    created_at       timestamp NOT NULL default now(),
+    UNIQUE (coupon_id, student_id),
+    CONSTRAINT fk_coupon
+        FOREIGN KEY (coupon_id)
+            REFERENCES coupons (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists learning_paths
+(
+    id          uuid         NOT NULL PRIMARY KEY,
+    title       varchar(140) NOT NULL,
+    description text,
+    price       INT,
+    created_at  timestamp    NOT NULL default now(),
+    updated_at  timestamp    NOT NULL default now(),
+    deleted_at  timestamp
+);
+
+create table if not exists learning_path_courses
+(
+    path_id   uuid NOT NULL,
+    course_id uuid NOT NULL,
+    position  INT  NOT NULL,
+    PRIMARY KEY (path_id, course_id),
+    UNIQUE (path_id, position),
+    CONSTRAINT fk_path
+        FOREIGN KEY (path_id)
+            REFERENCES learning_paths (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create table if not exists course_tags
+(
+    course_id uuid        NOT NULL,
+    tag       varchar(40) NOT NULL,
+    PRIMARY KEY (course_id, tag),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+create index if not exists course_tags_tag on course_tags (tag);
+
+create table if not exists course_tutors
+(
+    course_id  uuid        NOT NULL,
+    tutor_id   uuid        NOT NULL,
+    role       varchar(20) NOT NULL CHECK (role in ('owner', 'co_tutor', 'assistant')),
+    created_at timestamp   NOT NULL default now(),
+    updated_at timestamp   NOT NULL default now(),
+    PRIMARY KEY (course_id, tutor_id),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create unique index if not exists course_tutors_single_owner on course_tutors (course_id) where role = 'owner';
+create index if not exists course_tutors_tutor on course_tutors (tutor_id);
+
+-- The tutor of every existing course becomes its owner
+insert into course_tutors (course_id, tutor_id, role)
+select id, tutor_id, 'owner' from ezy_course_c4
+on conflict do nothing;
+
+create table if not exists tutor_ratings
+(
+    tutor_id   uuid      NOT NULL,
+    student_id uuid      NOT NULL,
+    rating     smallint  NOT NULL CHECK (rating between 1 and 5),
+    created_at timestamp NOT NULL default now(),
+    updated_at timestamp NOT NULL default now(),
+    PRIMARY KEY (tutor_id, student_id),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+-- Qualifications a tutor claims, reviewed by an admin before they count towards `verified`.
+create table if not exists tutor_credentials
+(
+    id              uuid         NOT NULL PRIMARY KEY,
+    tutor_id        uuid         NOT NULL,
+    kind            varchar(30)  NOT NULL CHECK (kind in ('degree', 'certificate')),
+    title           varchar(200) NOT NULL,
+    issuer          varchar(200) NOT NULL,
+    issued_on       date,
+    attachment_urls text[]       NOT NULL default '{}',
+    status          varchar(20)  NOT NULL default 'pending' CHECK (status in ('pending', 'approved', 'rejected')),
+    review_reason   text,
+    reviewed_at     timestamp,
+    created_at      timestamp    NOT NULL default now(),
+    updated_at      timestamp    NOT NULL default now(),
+    CHECK (status <> 'rejected' OR review_reason is not null),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create index if not exists tutor_credentials_tutor on tutor_credentials (tutor_id);
+create index if not exists tutor_credentials_pending on tutor_credentials (created_at) where status = 'pending';
+
+-- Every slug ever given to a tutor or course, so renamed resources can redirect old links.
+create table if not exists slugs
+(
+    slug       varchar(160) NOT NULL PRIMARY KEY,
+    tutor_id   uuid,
+    course_id  uuid,
+    created_at timestamp    NOT NULL default now(),
+    CHECK ((tutor_id is null) <> (course_id is null)),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
+
+-- Weekly hours a tutor offers private lessons, as wall clock times in the tutor's timezone.
+create table if not exists availability_rules
+(
+    id         uuid        NOT NULL PRIMARY KEY,
+    tutor_id   uuid        NOT NULL,
+    weekday    smallint    NOT NULL CHECK (weekday between 1 and 7),
+    start_time time        NOT NULL,
+    end_time   time        NOT NULL,
+    timezone   varchar(64) NOT NULL,
+    created_at timestamp   NOT NULL default now(),
+    CHECK (start_time < end_time),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create index if not exists availability_rules_tutor on availability_rules (tutor_id);
+
+-- One-off periods overriding the weekly rules, either extra hours or time off.
+create table if not exists availability_exceptions
+(
+    id         uuid         NOT NULL PRIMARY KEY,
+    tutor_id   uuid         NOT NULL,
+    starts_at  timestamptz  NOT NULL,
+    ends_at    timestamptz  NOT NULL,
+    available  boolean      NOT NULL,
+    reason     varchar(200),
+    created_at timestamp    NOT NULL default now(),
+    CHECK (starts_at < ends_at),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create index if not exists availability_exceptions_tutor on availability_exceptions (tutor_id, starts_at);
+
+create table if not exists lessons
+(
+    id           uuid        NOT NULL PRIMARY KEY,
+    tutor_id     uuid        NOT NULL,
+    student_id   uuid        NOT NULL,
+    starts_at    timestamptz NOT NULL,
+    ends_at      timestamptz NOT NULL,
+    status       varchar(20) NOT NULL default 'booked' CHECK (status in ('booked', 'cancelled')),
+    created_at   timestamp   NOT NULL default now(),
+    cancelled_at timestamp,
+    CHECK (starts_at < ends_at),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade,
+    -- Neither the tutor nor the student can be in two booked lessons at once
+    CONSTRAINT lessons_no_tutor_overlap
+        EXCLUDE USING gist (tutor_id WITH =, tstzrange(starts_at, ends_at) WITH &&) WHERE (status = 'booked'),
+    CONSTRAINT lessons_no_student_overlap
+        EXCLUDE USING gist (student_id WITH =, tstzrange(starts_at, ends_at) WITH &&) WHERE (status = 'booked')
+);
+
+-- Time between p_from and p_to the tutor is available for lessons, booked or not: the weekly
+-- rules expanded over the local days involved plus extra hours, minus time off.
+create or replace function tutor_availability(p_tutor_id uuid, p_from timestamptz, p_to timestamptz)
+    returns tstzmultirange
+    language sql stable as
+$$
+WITH open AS (
+    SELECT tstzrange((day::date + r.start_time) AT TIME ZONE r.timezone,
+                     (day::date + r.end_time) AT TIME ZONE r.timezone) AS period
+    FROM availability_rules r,
+         generate_series(((p_from AT TIME ZONE r.timezone)::date - 1)::timestamp,
+                         (p_to AT TIME ZONE r.timezone)::date::timestamp,
+                         interval '1 day') AS day
+    WHERE r.tutor_id = p_tutor_id AND extract(isodow from day) = r.weekday
+    UNION ALL
+    SELECT tstzrange(starts_at, ends_at)
+    FROM availability_exceptions
+    WHERE tutor_id = p_tutor_id AND available
+), closed AS (
+    SELECT tstzrange(starts_at, ends_at) AS period
+    FROM availability_exceptions
+    WHERE tutor_id = p_tutor_id AND NOT available
+)
+SELECT (coalesce((SELECT range_agg(period) FROM open), '{}')
+    - coalesce((SELECT range_agg(period) FROM closed), '{}'))
+    * tstzmultirange(tstzrange(p_from, p_to))
+$$;
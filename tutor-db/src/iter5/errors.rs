@@ -3,6 +3,8 @@ use log::{debug, error};
 use serde::Serialize;
 use actix_web::{error, Error, http::StatusCode, HttpResponse};
 use sqlx::error::Error as SQLxError;
+use sqlx::migrate::MigrateError;
+use json_patch::{PatchError, PatchErrorKind};
 
 #[derive(Debug, Serialize)]
 pub enum EzyTutorError {
@@ -10,6 +12,10 @@ pub enum EzyTutorError {
     ActixError(String),
     NotFound(String),
     InvalidInput(String),
+    Conflict(String),
+    Forbidden(String),
+    PreconditionFailed(String),
+    PreconditionRequired(String),
 }
 
 #[derive(Debug, Serialize)]
@@ -36,13 +42,29 @@ impl EzyTutorError {
                 debug!("Invalid params received: {:?}", msg);
                 msg.into()
             }
+            EzyTutorError::Conflict(msg) => {
+                debug!("Conflicting request received: {:?}", msg);
+                msg.into()
+            }
+            EzyTutorError::Forbidden(msg) => {
+                debug!("Forbidden request received: {:?}", msg);
+                msg.into()
+            }
+            EzyTutorError::PreconditionFailed(msg) => {
+                debug!("Stale request received: {:?}", msg);
+                msg.into()
+            }
+            EzyTutorError::PreconditionRequired(msg) => {
+                debug!("Unconditional request received: {:?}", msg);
+                msg.into()
+            }
         }
     }
 }
 
 impl Display for EzyTutorError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        write!(f, "{}", self.error_response())
     }
 }
 
@@ -54,6 +76,10 @@ impl error::ResponseError for EzyTutorError {
             }
             EzyTutorError::NotFound(_) => { StatusCode::NOT_FOUND }
             EzyTutorError::InvalidInput(_) => { StatusCode::BAD_REQUEST }
+            EzyTutorError::Conflict(_) => { StatusCode::CONFLICT }
+            EzyTutorError::Forbidden(_) => { StatusCode::FORBIDDEN }
+            EzyTutorError::PreconditionFailed(_) => { StatusCode::PRECONDITION_FAILED }
+            EzyTutorError::PreconditionRequired(_) => { StatusCode::PRECONDITION_REQUIRED }
         }
     }
 
@@ -70,15 +96,43 @@ impl From<actix_web::error::Error> for EzyTutorError {
     }
 }
 
+/// Postgres error code of rows refused by an exclusion constraint.
+const EXCLUSION_VIOLATION: &str = "23P01";
+
 impl From<SQLxError> for EzyTutorError {
     fn from(value: SQLxError) -> Self {
         match value {
             SQLxError::RowNotFound => {
                 EzyTutorError::NotFound("Requested resource not found".to_string())
             },
+            SQLxError::Database(ref db_error) if db_error.is_unique_violation() => {
+                EzyTutorError::Conflict("Resource already exists".to_string())
+            },
+            SQLxError::Database(ref db_error) if db_error.code().as_deref() == Some(EXCLUSION_VIOLATION) => {
+                EzyTutorError::Conflict("Resource overlaps an existing one".to_string())
+            },
             _  => {
                 EzyTutorError::DBError(value.to_string())
             }
         }
     }
+}
+
+impl From<MigrateError> for EzyTutorError {
+    fn from(value: MigrateError) -> Self {
+        EzyTutorError::DBError(value.to_string())
+    }
+}
+
+impl From<PatchError> for EzyTutorError {
+    fn from(value: PatchError) -> Self {
+        match value.kind {
+            PatchErrorKind::TestFailed => {
+                EzyTutorError::Conflict(format!("Patch test failed at {}", value.path))
+            },
+            _ => {
+                EzyTutorError::InvalidInput(format!("Patch operation {} failed: {}", value.operation, value))
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::models::booking::{AvailabilityExceptionDto, BookLessonDto, SlotQuery, WeeklyAvailabilityDto};
+use crate::services;
+
+pub async fn get_tutor_availability(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::get_availability(app_state, params.into_inner()).await
+        .map(|rules| HttpResponse::Ok().json(rules))
+}
+
+pub async fn set_tutor_availability(app_state: web::Data<AppState>, availability_dto: web::Json<WeeklyAvailabilityDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::set_availability(app_state, params.into_inner(), availability_dto.into()).await
+        .map(|rules| HttpResponse::Ok().json(rules))
+}
+
+pub async fn get_availability_exceptions(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::get_exceptions(app_state, params.into_inner()).await
+        .map(|exceptions| HttpResponse::Ok().json(exceptions))
+}
+
+pub async fn new_availability_exception(app_state: web::Data<AppState>, exception_dto: web::Json<AvailabilityExceptionDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::add_exception(app_state, params.into_inner(), exception_dto.into()).await
+        .map(|exception| HttpResponse::Created().json(exception))
+}
+
+pub async fn delete_availability_exception(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, exception_id) = params.into_inner();
+    services::booking_service::delete_exception(app_state, tutor_id, exception_id).await
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+pub async fn get_open_slots(app_state: web::Data<AppState>, params: web::Path<Uuid>, query: web::Query<SlotQuery>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::get_open_slots(app_state, params.into_inner(), query.into_inner()).await
+        .map(|slots| HttpResponse::Ok().json(slots))
+}
+
+pub async fn get_tutor_lessons(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::get_lessons(app_state, params.into_inner()).await
+        .map(|lessons| HttpResponse::Ok().json(lessons))
+}
+
+pub async fn book_lesson(app_state: web::Data<AppState>, lesson_dto: web::Json<BookLessonDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::booking_service::book_lesson(app_state, params.into_inner(), lesson_dto.into()).await
+        .map(|lesson| HttpResponse::Created().json(lesson))
+}
+
+pub async fn cancel_lesson(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, lesson_id) = params.into_inner();
+    services::booking_service::cancel_lesson(app_state, tutor_id, lesson_id).await
+        .map(|lesson| HttpResponse::Ok().json(lesson))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use chrono::{Duration, NaiveTime, Utc};
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::booking::{AvailabilityRuleDto, Lesson, OpenSlots};
+    use crate::models::tutor::CreateTutorDto;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn booked_slot_can_not_be_booked_twice() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: "Some private tutor".into(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+        let rules = (1..=7).map(|weekday| AvailabilityRuleDto {
+            weekday,
+            start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+        }).collect();
+        let availability_dto = web::Json(WeeklyAvailabilityDto { timezone: "Europe/Madrid".into(), rules });
+        set_tutor_availability(app_state.clone(), availability_dto, web::Path::from(tutor.id)).await.unwrap();
+
+        let day = (Utc::now() + Duration::days(10)).date_naive();
+        let query = SlotQuery { from: day, to: day, tz: Some("Europe/Madrid".into()), duration: Some(60) };
+        let resp = get_open_slots(app_state.clone(), web::Path::from(tutor.id), web::Query(query.clone())).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let open: OpenSlots = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let local_starts: Vec<_> = open.slots.iter().map(|slot| slot.local_starts_at).collect();
+        assert_eq!(local_starts, vec![day.and_hms_opt(9, 0, 0).unwrap(), day.and_hms_opt(10, 0, 0).unwrap()]);
+
+        let lesson_dto = BookLessonDto { student_id: Uuid::new_v4(), starts_at: open.slots[0].starts_at, duration_minutes: Some(60) };
+        let resp = book_lesson(app_state.clone(), web::Json(lesson_dto.clone()), web::Path::from(tutor.id)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let lesson: Lesson = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+
+        let other_student = BookLessonDto { student_id: Uuid::new_v4(), ..lesson_dto.clone() };
+        let resp = book_lesson(app_state.clone(), web::Json(other_student), web::Path::from(tutor.id)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::CONFLICT);
+
+        let too_early = BookLessonDto { starts_at: open.slots[0].starts_at - Duration::hours(2), ..lesson_dto };
+        let resp = book_lesson(app_state.clone(), web::Json(too_early), web::Path::from(tutor.id)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+
+        let resp = get_open_slots(app_state.clone(), web::Path::from(tutor.id), web::Query(query.clone())).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let open: OpenSlots = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(open.slots.len(), 1);
+
+        let resp = cancel_lesson(app_state.clone(), web::Path::from((tutor.id, lesson.id))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,106 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::models::coupon::{CreateCouponDto, QuoteQuery, RedeemCouponDto};
+use crate::services;
+
+pub async fn get_tutor_coupons(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::coupon_service::get_coupons(app_state, params.into_inner()).await
+        .map(|coupons| HttpResponse::Ok().json(coupons))
+}
+
+pub async fn new_coupon(app_state: web::Data<AppState>, coupon_dto: web::Json<CreateCouponDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::coupon_service::create_coupon(app_state, params.into_inner(), coupon_dto.into()).await
+        .map(|coupon| HttpResponse::Created().json(coupon))
+}
+
+pub async fn delete_coupon(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, coupon_id) = params.into_inner();
+    services::coupon_service::delete_coupon(app_state, tutor_id, coupon_id).await
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+pub async fn get_course_quote(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>, query: web::Query<QuoteQuery>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    services::coupon_service::quote(app_state, tutor_id, course_id, query.into_inner().code).await
+        .map(|quote| HttpResponse::Ok().json(quote))
+}
+
+pub async fn redeem_coupon(app_state: web::Data<AppState>, redeem_dto: web::Json<RedeemCouponDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    services::coupon_service::redeem(app_state, tutor_id, course_id, redeem_dto.into()).await
+        .map(|redemption| HttpResponse::Created().json(redemption))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::coupon::{Coupon, Quote};
+    use crate::models::course::CreateCourseDto;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn coupon_quote_and_redemption_limit() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let tutor_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap();
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id,
+            name: "Some priced course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: Some(90),
+            language: None,
+            level: None,
+        }).await.unwrap();
+
+        let code = format!("test-{}", &Uuid::new_v4().simple().to_string()[..8]);
+        let resp = new_coupon(app_state.clone(), web::Json(CreateCouponDto {
+            code: code.clone(),
+            discount_type: "percentage".into(),
+            amount: 25,
+            expires_at: None,
+            max_redemptions: Some(1),
+            course_ids: vec![course.id],
+        }), web::Path::from(tutor_id)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let coupon: Coupon = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(coupon.code, code.to_uppercase());
+
+        let query = web::Query(QuoteQuery { code: Some(code.clone()) });
+        let resp = get_course_quote(app_state.clone(), web::Path::from((tutor_id, course.id)), query).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let quote: Quote = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!((quote.price, quote.discount, quote.effective_price), (90, 23, 67));
+
+        let redemptions: Vec<_> = (0..5).map(|_| {
+            let app_state = app_state.clone();
+            let code = code.clone();
+            actix_rt::spawn(async move {
+                let dto = web::Json(RedeemCouponDto { code, student_id: Uuid::new_v4() });
+                redeem_coupon(app_state, dto, web::Path::from((tutor_id, course.id))).await.is_ok()
+            })
+        }).collect();
+        let mut redeemed = 0;
+        for redemption in redemptions {
+            if redemption.await.unwrap() {
+                redeemed += 1;
+            }
+        }
+        assert_eq!(redeemed, 1);
+    }
+}
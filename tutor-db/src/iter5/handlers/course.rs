@@ -1,37 +1,113 @@
+use std::collections::HashMap;
 use super::super::errors::EzyTutorError;
 use super::super::
 state::AppState;
 use actix_web::{HttpResponse, web};
+use actix_web::http::header::{AcceptLanguage, IfMatch, VARY};
+use json_patch::Patch;
 use uuid::Uuid;
-use crate::dbaccess::course;
-use crate::models::course::{CreateCourseDto, UpdateCourseDto};
+use crate::handlers::course_tutor::require_role;
+use crate::handlers::general::slug_response;
+use crate::i18n;
+use crate::models::course::{Course, CourseDetail, CreateCourseDto, UpdateCourseDto};
+use crate::models::course_tutor::{CO_TUTOR, OWNER};
+use crate::models::include::{IncludeQuery, TUTOR};
+use crate::models::slug::SlugOwner;
+use crate::models::tutor::Tutor;
+use crate::precondition::{etag, representation_etag, ExpectedVersion};
+use crate::replica::Consistency;
 
 pub async fn new_course(course_dto: web::Json<CreateCourseDto>, app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
     let dto: CreateCourseDto = course_dto.into();
-    let course = course::new_course(&app_state.db, dto).await?;
+    let course = app_state.courses.new_course(dto).await?;
     Ok(HttpResponse::Created().json(course))
 }
 
-pub async fn get_courses_for_tutor(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
-    let courses = course::get_courses_by_tutor(&app_state.db, params.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(courses))
+/// Embeds the owner of every course when `include` asks for it, with one query whatever the
+/// number of courses.
+async fn with_tutors(app_state: &web::Data<AppState>, courses: Vec<Course>, include: &IncludeQuery) -> Result<Vec<CourseDetail>, EzyTutorError> {
+    if !include.wants(TUTOR, &[TUTOR])? {
+        return Ok(courses.into_iter().map(|course| CourseDetail { course, tutor: None }).collect());
+    }
+    let tutor_ids: Vec<Uuid> = courses.iter().map(|course| course.tutor_id).collect();
+    let tutors: HashMap<Uuid, Tutor> = app_state.tutors.get_tutors_by_ids(&tutor_ids).await?
+        .into_iter()
+        .map(|tutor| (tutor.id, tutor))
+        .collect();
+    Ok(courses.into_iter().map(|course| {
+        let tutor = tutors.get(&course.tutor_id).cloned();
+        CourseDetail { course, tutor }
+    }).collect())
+}
+
+async fn detail(app_state: &web::Data<AppState>, course: Course, include: &IncludeQuery) -> Result<CourseDetail, EzyTutorError> {
+    with_tutors(app_state, vec![course], include).await?
+        .pop()
+        .ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))
+}
+
+pub async fn get_courses_for_tutor(app_state: web::Data<AppState>, params: web::Path<Uuid>, include: web::Query<IncludeQuery>, accept_language: Option<web::Header<AcceptLanguage>>, consistency: Consistency) -> Result<HttpResponse, EzyTutorError> {
+    let courses = app_state.courses_for(consistency).get_courses_by_tutor(params.into_inner()).await?;
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let course_ids: Vec<Uuid> = courses.iter().map(|course| course.id).collect();
+    let translations = app_state.courses_for(consistency).get_translations_for_courses(&course_ids, &locales).await?;
+    let courses: Vec<_> = courses.into_iter().map(|course| course.localize(&translations, &locales)).collect();
+    let courses = with_tutors(&app_state, courses, &include).await?;
+    Ok(HttpResponse::Ok().insert_header((VARY, "Accept-Language")).json(courses))
 }
 
-pub async fn get_course_detail(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+pub async fn get_course_detail(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>, include: web::Query<IncludeQuery>, accept_language: Option<web::Header<AcceptLanguage>>, consistency: Consistency) -> Result<HttpResponse, EzyTutorError> {
     let (tutor_id, course_id) = params.into_inner();
-    let course = course::get_course(&app_state.db, tutor_id, course_id).await?;
-    Ok(HttpResponse::Ok().json(course))
+    let course = app_state.courses_for(consistency).get_course(tutor_id, course_id).await?;
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let translations = app_state.courses_for(consistency).get_translations_for_courses(&[course.id], &locales).await?;
+    let etag = representation_etag(course.version, &locales, &include.included(&[TUTOR])?);
+    let course = detail(&app_state, course.localize(&translations, &locales), &include).await?;
+    Ok(HttpResponse::Ok().insert_header((VARY, "Accept-Language")).insert_header(etag).json(course))
+}
+
+pub async fn get_course_by_slug(app_state: web::Data<AppState>, params: web::Path<String>, include: web::Query<IncludeQuery>, accept_language: Option<web::Header<AcceptLanguage>>) -> Result<HttpResponse, EzyTutorError> {
+    let requested = params.into_inner();
+    let course = match app_state.courses.find_slug(&requested).await? {
+        SlugOwner::Course(course_id) => app_state.courses.get_courses_by_ids(&[course_id]).await?.pop(),
+        SlugOwner::Tutor(_) => None,
+    }.ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))?;
+
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let translations = app_state.courses.get_translations_for_courses(&[course.id], &locales).await?;
+    let current = course.slug.clone();
+    let etag = representation_etag(course.version, &locales, &include.included(&[TUTOR])?);
+    let course = detail(&app_state, course.localize(&translations, &locales), &include).await?;
+    Ok(slug_response(&requested, &current, etag, course))
 }
 
-pub async fn update_course_detail(app_state: web::Data<AppState>, course_dto: web::Json<UpdateCourseDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+pub async fn update_course_detail(app_state: web::Data<AppState>, course_dto: web::Json<UpdateCourseDto>, params: web::Path<(Uuid, Uuid)>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
     let (tutor_id, course_id) = params.into_inner();
-    let course = course::update_course(&app_state.db, tutor_id, course_id, course_dto.into()).await?;
-    Ok(HttpResponse::Ok().json(course))
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let course = app_state.courses.update_course(tutor_id, course_id, course_dto.into(), &expected).await?;
+    Ok(HttpResponse::Ok().insert_header(etag(course.version)).json(course))
 }
 
-pub async fn soft_delete_course(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+/// Members of a course that a JSON Patch can test but not change.
+const READ_ONLY_MEMBERS: [&str; 8] = ["id", "tutor_id", "posted_time", "created_at", "updated_at", "slug", "description_html", "version"];
+
+pub async fn json_patch_course_detail(app_state: web::Data<AppState>, patch: web::Json<Patch>, params: web::Path<(Uuid, Uuid)>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
     let (tutor_id, course_id) = params.into_inner();
-    let course = course::soft_delete_course(&app_state.db, tutor_id, course_id).await?;
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let course = app_state.courses.update_course_with(tutor_id, course_id, Box::new(|current| {
+        expected.check(current.version)?;
+        crate::patch::apply(current, &patch, &READ_ONLY_MEMBERS)
+    })).await?;
+    Ok(HttpResponse::Ok().insert_header(etag(course.version)).json(course))
+}
+
+pub async fn soft_delete_course(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER]).await?;
+    let course = app_state.courses.soft_delete_course(tutor_id, course_id, &expected).await?;
     Ok(HttpResponse::Ok().json(course))
 }
 
@@ -39,24 +115,47 @@ pub async fn soft_delete_course(app_state: web::Data<AppState>, params: web::Pat
 mod test {
     use std::env;
     use std::str::FromStr;
-    use std::sync::Mutex;
+    use std::sync::Arc;
     use actix_web::body::MessageBody;
+    use actix_web::{test, App};
+    use actix_web::http::header::{q, EntityTag, QualityItem, CONTENT_TYPE, ETAG, LOCATION};
     use actix_web::http::StatusCode;
     use actix_web::ResponseError;
     use dotenv::dotenv;
     use log::debug;
     use sqlx::PgPool;
-    use crate::models::course::Course;
     use serde_json::json;
+    use crate::models::course::UpsertCourseTranslationDto;
+    use crate::models::tutor::CreateTutorDto;
+    use crate::repository::memory::InMemoryRepository;
     use super::*;
 
     pub fn init_test_debug() {
-        match env_logger::try_init() {
-            Ok(_) => {
-                env::set_var("RUST_LOG", "debug");
-                debug!("Debugger Up 👍");
-            }
-            Err(_) => {}
+        if env_logger::try_init().is_ok() {
+            env::set_var("RUST_LOG", "debug");
+            debug!("Debugger Up 👍");
+        }
+    }
+
+    async fn some_tutor(app_state: &web::Data<AppState>) -> Tutor {
+        app_state.tutors.create(CreateTutorDto {
+            name: "Some tutor".into(),
+            pic_url: "http://s3.amazon.aws.com/pic1".into(),
+            profile: "Some profile".into(),
+        }).await.unwrap()
+    }
+
+    fn some_course(tutor_id: Uuid) -> CreateCourseDto {
+        CreateCourseDto {
+            tutor_id,
+            name: "Some course name".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
         }
     }
 
@@ -65,17 +164,11 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
 
         let course = web::Json(CreateCourseDto {
-            tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+            tutor_id: tutor.id,
             name: "Some course name".into(),
             description: None,
             format: None,
@@ -95,19 +188,12 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
 
         let tutor_id: web::Path<Uuid> = web::Path::from(Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e74").unwrap());
 
-        let resp = get_courses_for_tutor(app_state, tutor_id).await.unwrap();
-        let actual_status = resp.status().clone();
+        let resp = get_courses_for_tutor(app_state, tutor_id, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await.unwrap();
+        let actual_status = resp.status();
 
 
         assert_eq!(actual_status, StatusCode::OK);
@@ -123,18 +209,17 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
+        let course = app_state.courses.new_course(some_course(tutor.id)).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
-
-        let tutor_id: web::Path<Uuid> = web::Path::from(Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap());
-        let resp = get_courses_for_tutor(app_state, tutor_id).await.unwrap();
+        let tutor_id: web::Path<Uuid> = web::Path::from(tutor.id);
+        let resp = get_courses_for_tutor(app_state, tutor_id, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let actual: Vec<Course> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(actual, vec![course]);
     }
 
     #[actix_rt::test]
@@ -142,17 +227,10 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(), Uuid::from_str("70c57639-680a-44e8-a15b-e879d38aa854").unwrap()));
-        let resp = get_course_detail(app_state, params).await;
+        let resp = get_course_detail(app_state, params, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await;
         if resp.is_err() {
             assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND)
         } else {
@@ -165,18 +243,58 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
+        let course = app_state.courses.new_course(some_course(tutor.id)).await.unwrap();
+
+        let params: web::Path<(Uuid, Uuid)> = web::Path::from((tutor.id, course.id));
+        let resp = get_course_detail(app_state, params, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn get_course_detail_localized() {
+        dotenv().ok();
+        init_test_debug();
+
         let database_url = env::var("DATABASE_URL").expect("Database url is not set");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(), Uuid::from_str("70c57639-680a-44e8-a15b-e879d38aa856").unwrap()));
-        let resp = get_course_detail(app_state, params).await.unwrap();
+        let accept_language = web::Header(AcceptLanguage(vec![
+            QualityItem::max("fr".parse().unwrap()),
+            QualityItem::new("en-US".parse().unwrap(), q(0.5)),
+        ]));
+        let resp = get_course_detail(app_state, params, web::Query(IncludeQuery::default()), Some(accept_language), Consistency::Eventual).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let actual_course: Course = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(actual_course.name, "Basics of optics");
+    }
+
+    #[actix_rt::test]
+    async fn course_by_slug_is_localized() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
+        let course = app_state.courses.new_course(some_course(tutor.id)).await.unwrap();
+        app_state.courses.upsert_translation(course.id, "es".into(), UpsertCourseTranslationDto {
+            name: "Algún curso".into(),
+            description: None,
+        }).await.unwrap();
+
+        let accept_language = web::Header(AcceptLanguage(vec![QualityItem::max("es-AR".parse().unwrap())]));
+        let resp = get_course_by_slug(app_state.clone(), web::Path::from(course.slug.clone()), web::Query(IncludeQuery::default()), Some(accept_language)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(ETAG).unwrap(), "\"1;es-ar,es;\"");
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let actual_course: Course = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(actual_course.name, "Algún curso");
+
+        let resp = get_course_by_slug(app_state.clone(), web::Path::from(tutor.slug), web::Query(IncludeQuery::default()), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND);
     }
 
     #[actix_rt::test]
@@ -184,17 +302,11 @@ mod test {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
 
         let course = web::Json(CreateCourseDto {
-            tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+            tutor_id: tutor.id,
             name: "Some course name".into(),
             description: None,
             format: None,
@@ -206,10 +318,10 @@ mod test {
         });
 
         let resp = new_course(course, app_state.clone()).await.unwrap();
-        let actual_status = resp.status().clone();
+        let actual_status = resp.status();
         let body = &resp.into_body().try_into_bytes().unwrap()[..];
         let actual = std::str::from_utf8(body).unwrap();
-        let course: Course = serde_json::from_str::<Course>(actual).unwrap().into();
+        let course: Course = serde_json::from_str::<Course>(actual).unwrap();
         assert_eq!(actual_status, StatusCode::CREATED);
         debug!("Created tutor_id: {} | course_id: {}", course.tutor_id, course.id);
 
@@ -227,11 +339,11 @@ mod test {
         });
 
 
-        let resp = update_course_detail(app_state.clone(), update_course, params).await.unwrap();
-        let actual_status = resp.status().clone();
+        let resp = update_course_detail(app_state.clone(), update_course, params, None).await.unwrap();
+        let actual_status = resp.status();
         let body = &resp.into_body().try_into_bytes().unwrap()[..];
         let actual = std::str::from_utf8(body).unwrap();
-        let actual_course: Course = serde_json::from_str::<Course>(actual).unwrap().into();
+        let actual_course: Course = serde_json::from_str::<Course>(actual).unwrap();
 
         assert_eq!(actual_status, StatusCode::OK);
         debug!("Updated tutor_id: {} | course_id: {}", course.tutor_id, course.id);
@@ -247,20 +359,23 @@ mod test {
             price: Some(55),
             language: Some("test lang".into()),
             level: Some("beginners".into()),
-            posted_time: actual_course.posted_time.clone(),
-            created_at: actual_course.created_at.clone(),
-            updated_at: actual_course.updated_at.clone(),
+            posted_time: actual_course.posted_time,
+            created_at: actual_course.created_at,
+            updated_at: actual_course.updated_at,
             deleted_at: None,
+            slug: actual_course.slug.clone(),
+            description_html: Some("<p>Some course name updated by tests</p>\n".into()),
+            version: 2,
         };
 
         assert_eq!(expected_course.clone(), actual_course);
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((course.tutor_id, course.id));
-        let resp = get_course_detail(app_state.clone(), params).await.unwrap();
-        let actual_status = resp.status().clone();
+        let resp = get_course_detail(app_state.clone(), params, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await.unwrap();
+        let actual_status = resp.status();
         let body = &resp.into_body().try_into_bytes().unwrap()[..];
         let actual = std::str::from_utf8(body).unwrap();
-        let actual_course: Course = serde_json::from_str::<Course>(actual).unwrap().into();
+        let actual_course: Course = serde_json::from_str::<Course>(actual).unwrap();
 
         assert_eq!(actual_status, StatusCode::OK);
         debug!("Get updated tutor_id: {} | course_id: {}", course.tutor_id, course.id);
@@ -268,18 +383,57 @@ mod test {
     }
 
     #[actix_rt::test]
-    async fn update_course_failed_due_to_wrong_course_id() {
+    async fn renamed_course_slug_redirects() {
         dotenv().ok();
         init_test_debug();
 
         let database_url = env::var("DATABASE_URL").expect("Database url is not set");
         let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let marker = Uuid::new_v4().simple().to_string();
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+            name: format!("Año de Óptica {}", marker),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+        assert_eq!(course.slug, format!("ano-de-optica-{}", marker));
+
+        let update_course = web::Json(UpdateCourseDto {
+            name: format!("Óptica avanzada {}", marker),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
         });
+        let params: web::Path<(Uuid, Uuid)> = web::Path::from((course.tutor_id, course.id));
+        update_course_detail(app_state.clone(), update_course, params, None).await.unwrap();
+
+        let resp = get_course_by_slug(app_state.clone(), web::Path::from(course.slug.clone()), web::Query(IncludeQuery::default()), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+        let new_slug = format!("optica-avanzada-{}", marker);
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), new_slug.as_str());
+
+        let resp = get_course_by_slug(app_state.clone(), web::Path::from(new_slug), web::Query(IncludeQuery::default()), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn update_course_failed_due_to_wrong_course_id() {
+        dotenv().ok();
+        init_test_debug();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e55").unwrap(), Uuid::from_str("70c57639-680a-44e8-a15b-e879d38aa886").unwrap()));
         let update_course = web::Json(UpdateCourseDto {
@@ -294,7 +448,7 @@ mod test {
         });
 
 
-        let resp = update_course_detail(app_state.clone(), update_course, params).await;
+        let resp = update_course_detail(app_state.clone(), update_course, params, None).await;
         if resp.is_err() {
             assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND)
         } else {
@@ -303,21 +457,69 @@ mod test {
     }
 
     #[actix_rt::test]
-    async fn soft_delete_course_success() {
+    async fn reads_follow_the_client_consistency() {
+        // A replica that has not caught up with anything yet
+        let replica = Arc::new(InMemoryRepository::new());
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory().with_replica(replica));
+        let tutor = some_tutor(&app_state).await;
+        let course = app_state.courses.new_course(some_course(tutor.id)).await.unwrap();
+
+        let params = || web::Path::from((tutor.id, course.id));
+        let resp = get_course_detail(app_state.clone(), params(), web::Query(IncludeQuery::default()), None, Consistency::Eventual).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND);
+        let resp = get_course_detail(app_state.clone(), params(), web::Query(IncludeQuery::default()), None, Consistency::ReadYourWrites).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn stale_if_match_is_refused() {
         dotenv().ok();
         init_test_debug();
 
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
+        let app_state = web::Data::new(AppState::in_memory().with_if_match_required(true));
+        let tutor = some_tutor(&app_state).await;
+        let course = app_state.courses.new_course(some_course(tutor.id)).await.unwrap();
+        let params = || web::Path::from((tutor.id, course.id));
+        let if_match = |version: i32| Some(web::Header(IfMatch::Items(vec![EntityTag::new_strong(version.to_string())])));
+        let rename = || web::Json(UpdateCourseDto {
+            name: "Some renamed course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
         });
 
+        let resp = get_course_detail(app_state.clone(), params(), web::Query(IncludeQuery::default()), None, Consistency::Eventual).await.unwrap();
+        assert_eq!(resp.headers().get(ETAG).unwrap(), "\"1\"");
+        let include_tutor = web::Query(IncludeQuery { include: Some(TUTOR.into()) });
+        let resp = get_course_detail(app_state.clone(), params(), include_tutor, None, Consistency::Eventual).await.unwrap();
+        assert_eq!(resp.headers().get(ETAG).unwrap(), "\"1;;tutor\"");
+        let resp = update_course_detail(app_state.clone(), rename(), params(), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::PRECONDITION_REQUIRED);
+
+        let read_tag = Some(web::Header(IfMatch::Items(vec![EntityTag::new_strong("1;;tutor".into())])));
+        let resp = update_course_detail(app_state.clone(), rename(), params(), read_tag).await.unwrap();
+        assert_eq!(resp.headers().get(ETAG).unwrap(), "\"2\"");
+        let resp = update_course_detail(app_state.clone(), rename(), params(), if_match(1)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::PRECONDITION_FAILED);
+        let resp = soft_delete_course(app_state.clone(), params(), if_match(1)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::PRECONDITION_FAILED);
+        soft_delete_course(app_state.clone(), params(), if_match(2)).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn soft_delete_course_success() {
+        dotenv().ok();
+        init_test_debug();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+        let tutor = some_tutor(&app_state).await;
+
         let course = web::Json(CreateCourseDto {
-            tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+            tutor_id: tutor.id,
             name: "Some course name".into(),
             description: None,
             format: None,
@@ -329,21 +531,21 @@ mod test {
         });
 
         let resp = new_course(course, app_state.clone()).await.unwrap();
-        let actual_status = resp.status().clone();
+        let actual_status = resp.status();
         let body = &resp.into_body().try_into_bytes().unwrap()[..];
         let actual = std::str::from_utf8(body).unwrap();
-        let course: Course = serde_json::from_str::<Course>(actual).unwrap().into();
+        let course: Course = serde_json::from_str::<Course>(actual).unwrap();
         assert_eq!(actual_status, StatusCode::CREATED);
         debug!("Created tutor_id: {} | course_id: {}", course.tutor_id, course.id);
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((course.tutor_id, course.id));
-        let resp = soft_delete_course(app_state.clone(), params).await.unwrap();
+        let resp = soft_delete_course(app_state.clone(), params, None).await.unwrap();
 
         assert_eq!(resp.status(), StatusCode::OK);
         debug!("Deleted tutor_id: {} | course_id: {}", course.tutor_id, course.id);
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((course.tutor_id, course.id));
-        let resp = get_course_detail(app_state.clone(), params).await;
+        let resp = get_course_detail(app_state.clone(), params, web::Query(IncludeQuery::default()), None, Consistency::Eventual).await;
         debug!("Already deleted tutor_id: {} | course_id: {}", course.tutor_id, course.id);
         if resp.is_err() {
             assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND)
@@ -356,21 +558,62 @@ mod test {
     async fn soft_delete_course_failed_due_to_wrong_course_id() {
         dotenv().ok();
         init_test_debug();
-        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
 
-        let app_state: web::Data<AppState> = web::Data::new(AppState {
-            health_check_response: "".to_string(),
-            visit_count: Mutex::new(0),
-            db: db_pool,
-        });
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
 
         let params: web::Path<(Uuid, Uuid)> = web::Path::from((Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e55").unwrap(), Uuid::from_str("70c57639-680a-44e8-a15b-e879d38aa886").unwrap()));
-        let resp = soft_delete_course(app_state.clone(), params).await;
+        let resp = soft_delete_course(app_state.clone(), params, None).await;
         if resp.is_err() {
             assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND)
         } else {
             assert_eq!(false, true)
         }
     }
+
+    #[actix_rt::test]
+    async fn json_patch_is_routed_and_atomic() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+        let tutor_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap();
+        let created = app_state.courses.new_course(CreateCourseDto {
+            tutor_id,
+            name: "Some patched course".into(),
+            description: Some("Some description".into()),
+            format: None,
+            structure: None,
+            duration: None,
+            price: Some(100),
+            language: None,
+            level: None,
+        }).await.unwrap();
+
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(crate::routes::course_routes)).await;
+        let uri = format!("/courses/{}/{}", tutor_id, created.id);
+        let req = test::TestRequest::patch().uri(&uri)
+            .insert_header((CONTENT_TYPE, crate::patch::JSON_PATCH))
+            .set_payload(json!([
+                { "op": "replace", "path": "/price", "value": 80 },
+                { "op": "test", "path": "/name", "value": "Some other course" },
+            ]).to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let unchanged = app_state.courses.get_course(tutor_id, created.id).await.unwrap();
+        assert_eq!(unchanged.price, Some(100));
+
+        let req = test::TestRequest::patch().uri(&uri)
+            .insert_header((CONTENT_TYPE, crate::patch::JSON_PATCH))
+            .set_payload(json!([
+                { "op": "test", "path": "/name", "value": "Some patched course" },
+                { "op": "replace", "path": "/price", "value": 80 },
+                { "op": "remove", "path": "/description" },
+            ]).to_string())
+            .to_request();
+        let patched: Course = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(patched.price, Some(80));
+        assert_eq!(patched.description, None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,23 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::dbaccess::course_tag;
+use crate::handlers::course_tutor::require_role;
+use crate::models::course::CourseTagsDto;
+use crate::models::course_tutor::{CO_TUTOR, OWNER};
+
+pub async fn get_course_tags(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    app_state.courses.get_course(tutor_id, course_id).await?;
+    let tags = course_tag::get_tags(&app_state.db, course_id).await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+pub async fn set_course_tags(app_state: web::Data<AppState>, tags_dto: web::Json<CourseTagsDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    let tags = CourseTagsDto::from(tags_dto).normalized()?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let tags = course_tag::replace_tags(&app_state.db, course_id, &tags).await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
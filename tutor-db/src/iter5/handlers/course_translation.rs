@@ -0,0 +1,31 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::handlers::course_tutor::require_role;
+use crate::i18n;
+use crate::models::course::UpsertCourseTranslationDto;
+use crate::models::course_tutor::{CO_TUTOR, OWNER};
+
+pub async fn get_course_translations(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    app_state.courses.get_course(tutor_id, course_id).await?;
+    let translations = app_state.courses.get_translations(course_id).await?;
+    Ok(HttpResponse::Ok().json(translations))
+}
+
+pub async fn upsert_course_translation(app_state: web::Data<AppState>, translation_dto: web::Json<UpsertCourseTranslationDto>, params: web::Path<(Uuid, Uuid, String)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, locale) = params.into_inner();
+    let locale = i18n::normalize_locale(&locale)?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let translation = app_state.courses.upsert_translation(course_id, locale, translation_dto.into()).await?;
+    Ok(HttpResponse::Ok().json(translation))
+}
+
+pub async fn delete_course_translation(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid, String)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, locale) = params.into_inner();
+    let locale = i18n::normalize_locale(&locale)?;
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let translation = app_state.courses.delete_translation(course_id, locale).await?;
+    Ok(HttpResponse::Ok().json(translation))
+}
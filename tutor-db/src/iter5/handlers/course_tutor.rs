@@ -0,0 +1,154 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::dbaccess::course_tutor;
+use crate::models::course_tutor::{ASSISTANT, CO_TUTOR, CourseTutorRoleDto, OWNER};
+
+/// Fails with NotFound when the tutor does not teach the course, and with Forbidden when it
+/// does but with a role other than `roles`.
+pub async fn require_role(app_state: &web::Data<AppState>, tutor_id: Uuid, course_id: Uuid, roles: &[&str]) -> Result<(), EzyTutorError> {
+    match app_state.courses.role_of(course_id, tutor_id).await? {
+        Some(role) if roles.contains(&role.as_str()) => Ok(()),
+        Some(role) => Err(EzyTutorError::Forbidden(format!("A course {} is not allowed to do this", role))),
+        None => Err(EzyTutorError::NotFound("Requested resource not found".to_string())),
+    }
+}
+
+pub async fn get_course_tutors(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR, ASSISTANT]).await?;
+    let tutors = course_tutor::get_course_tutors(&app_state.db, course_id).await?;
+    Ok(HttpResponse::Ok().json(tutors))
+}
+
+pub async fn set_course_tutor(app_state: web::Data<AppState>, role_dto: web::Json<CourseTutorRoleDto>, params: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, member_id) = params.into_inner();
+    let role_dto: CourseTutorRoleDto = role_dto.into();
+    if role_dto.role != CO_TUTOR && role_dto.role != ASSISTANT {
+        return Err(EzyTutorError::InvalidInput("Role must be either co_tutor or assistant".to_string()));
+    }
+    require_role(&app_state, tutor_id, course_id, &[OWNER]).await?;
+    app_state.tutors.by_id(member_id).await?;
+    let member = course_tutor::set_course_tutor(&app_state.db, course_id, member_id, &role_dto.role).await?;
+    Ok(HttpResponse::Ok().json(member))
+}
+
+pub async fn remove_course_tutor(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, member_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER]).await?;
+    let member = course_tutor::remove_course_tutor(&app_state.db, course_id, member_id).await?;
+    Ok(HttpResponse::Ok().json(member))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::handlers::course::{soft_delete_course, update_course_detail};
+    use crate::handlers::course_tag::set_course_tags;
+    use crate::handlers::course_translation::{delete_course_translation, upsert_course_translation};
+    use crate::handlers::enrollment::{enroll_student, join_course_waitlist, leave_course_waitlist, set_course_capacity, withdraw_student};
+    use crate::models::course::{CourseTagsDto, CreateCourseDto, UpdateCourseDto, UpsertCourseTranslationDto};
+    use crate::models::enrollment::{SetCapacityDto, StudentDto};
+    use super::*;
+
+    #[actix_rt::test]
+    async fn co_tutor_can_edit_but_not_delete() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let owner_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap();
+        let co_tutor_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e62").unwrap();
+        let created = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: owner_id,
+            name: "Some shared course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+
+        let resp = set_course_tutor(app_state.clone(), web::Json(CourseTutorRoleDto { role: CO_TUTOR.into() }),
+                                    web::Path::from((owner_id, created.id, co_tutor_id))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let listed = app_state.courses.get_courses_by_tutor(co_tutor_id).await.unwrap();
+        assert!(listed.iter().any(|course| course.id == created.id));
+
+        let update_dto = web::Json(UpdateCourseDto {
+            name: "Some shared course, revised".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        });
+        let resp = update_course_detail(app_state.clone(), update_dto, web::Path::from((co_tutor_id, created.id)), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = soft_delete_course(app_state.clone(), web::Path::from((co_tutor_id, created.id)), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+
+        let resp = soft_delete_course(app_state.clone(), web::Path::from((owner_id, created.id)), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn assistant_can_not_change_course_sub_resources() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let owner_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap();
+        let assistant_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e62").unwrap();
+        let created = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: owner_id,
+            name: "Some assisted course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+        set_course_tutor(app_state.clone(), web::Json(CourseTutorRoleDto { role: ASSISTANT.into() }),
+                         web::Path::from((owner_id, created.id, assistant_id))).await.unwrap();
+
+        let course_path = || web::Path::from((assistant_id, created.id));
+        let locale_path = || web::Path::from((assistant_id, created.id, "en".to_string()));
+        let student_path = || web::Path::from((assistant_id, created.id, Uuid::new_v4()));
+        let student = || web::Json(StudentDto { student_id: Uuid::new_v4() });
+        let translation = web::Json(UpsertCourseTranslationDto { name: "Some translated course".into(), description: None });
+        let responses = vec![
+            upsert_course_translation(app_state.clone(), translation, locale_path()).await,
+            delete_course_translation(app_state.clone(), locale_path()).await,
+            set_course_tags(app_state.clone(), web::Json(CourseTagsDto { tags: vec!["optics".into()] }), course_path()).await,
+            set_course_capacity(app_state.clone(), web::Json(SetCapacityDto { capacity: 1 }), course_path()).await,
+            enroll_student(app_state.clone(), student(), course_path()).await,
+            withdraw_student(app_state.clone(), student_path()).await,
+            join_course_waitlist(app_state.clone(), student(), course_path()).await,
+            leave_course_waitlist(app_state.clone(), student_path()).await,
+        ];
+        for resp in responses {
+            assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+        }
+    }
+}
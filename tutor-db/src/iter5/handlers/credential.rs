@@ -0,0 +1,99 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::models::credential::{CredentialDto, ReviewCredentialDto};
+use crate::services;
+
+pub async fn get_tutor_credentials(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::credential_service::get_credentials(app_state, params.into_inner()).await
+        .map(|credentials| HttpResponse::Ok().json(credentials))
+}
+
+/// Admin review queue of the credentials waiting for a decision.
+pub async fn get_pending_credentials(app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
+    services::credential_service::get_pending(app_state).await
+        .map(|credentials| HttpResponse::Ok().json(credentials))
+}
+
+pub async fn new_credential(app_state: web::Data<AppState>, credential_dto: web::Json<CredentialDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::credential_service::add_credential(app_state, params.into_inner(), credential_dto.into()).await
+        .map(|credential| HttpResponse::Created().json(credential))
+}
+
+pub async fn review_credential(app_state: web::Data<AppState>, review_dto: web::Json<ReviewCredentialDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, credential_id) = params.into_inner();
+    services::credential_service::review(app_state, tutor_id, credential_id, review_dto.into()).await
+        .map(|credential| HttpResponse::Ok().json(credential))
+}
+
+pub async fn delete_credential(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, credential_id) = params.into_inner();
+    services::credential_service::delete_credential(app_state, tutor_id, credential_id).await
+        .map(|_| HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::credential::{APPROVED, Credential, DEGREE, PENDING, REJECTED};
+    use crate::models::tutor::{CreateTutorDto, TutorListQuery};
+    use crate::replica::Consistency;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn approved_credential_verifies_tutor() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let name = format!("Credentials {}", Uuid::new_v4().simple());
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: name.clone(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+        assert!(!tutor.verified);
+
+        let credential_dto = web::Json(CredentialDto {
+            kind: DEGREE.into(),
+            title: "Licenciatura en Filosofía".into(),
+            issuer: "Universidad de Salamanca".into(),
+            issued_on: None,
+            attachment_urls: vec!["https://example.com/diploma.pdf".into()],
+        });
+        let resp = new_credential(app_state.clone(), credential_dto, web::Path::from(tutor.id)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let credential: Credential = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(credential.status, PENDING);
+
+        let resp = get_pending_credentials(app_state.clone()).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let pending: Vec<Credential> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert!(pending.iter().any(|pending| pending.id == credential.id));
+
+        let rejection = web::Json(ReviewCredentialDto { status: REJECTED.into(), reason: None });
+        let resp = review_credential(app_state.clone(), rejection, web::Path::from((tutor.id, credential.id))).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+
+        let approval = web::Json(ReviewCredentialDto { status: APPROVED.into(), reason: None });
+        review_credential(app_state.clone(), approval, web::Path::from((tutor.id, credential.id))).await.unwrap();
+        let verified_only = TutorListQuery { name: Some(name.clone()), verified: Some(true), ..Default::default() };
+        let page = services::tutor_service::get_tutors(app_state.clone(), &verified_only, Consistency::ReadYourWrites).await.unwrap();
+        assert_eq!(page.tutors.iter().map(|tutor| tutor.id).collect::<Vec<_>>(), vec![tutor.id]);
+        assert!(page.tutors[0].verified);
+
+        delete_credential(app_state.clone(), web::Path::from((tutor.id, credential.id))).await.unwrap();
+        let page = services::tutor_service::get_tutors(app_state.clone(), &verified_only, Consistency::ReadYourWrites).await.unwrap();
+        assert!(page.tutors.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::dbaccess::enrollment;
+use crate::handlers::course_tutor::require_role;
+use crate::models::course_tutor::{CO_TUTOR, OWNER};
+use crate::models::enrollment::{SetCapacityDto, StudentDto};
+
+pub async fn get_course_capacity(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    app_state.courses.get_course(tutor_id, course_id).await?;
+    let capacity = enrollment::get_capacity(&app_state.db, course_id).await?;
+    Ok(HttpResponse::Ok().json(capacity))
+}
+
+pub async fn set_course_capacity(app_state: web::Data<AppState>, capacity_dto: web::Json<SetCapacityDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let dto: SetCapacityDto = capacity_dto.into();
+    let capacity = enrollment::set_capacity(&app_state.db, course_id, dto.capacity).await?;
+    Ok(HttpResponse::Ok().json(capacity))
+}
+
+pub async fn enroll_student(app_state: web::Data<AppState>, student_dto: web::Json<StudentDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let dto: StudentDto = student_dto.into();
+    let enrollment = enrollment::enroll(&app_state.db, course_id, dto.student_id).await?;
+    Ok(HttpResponse::Created().json(enrollment))
+}
+
+pub async fn withdraw_student(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, student_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let enrollment = enrollment::withdraw(&app_state.db, course_id, student_id).await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+pub async fn get_course_waitlist(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    app_state.courses.get_course(tutor_id, course_id).await?;
+    let entries = enrollment::get_waitlist(&app_state.db, course_id).await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub async fn join_course_waitlist(app_state: web::Data<AppState>, student_dto: web::Json<StudentDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let dto: StudentDto = student_dto.into();
+    let entry = enrollment::join_waitlist(&app_state.db, course_id, dto.student_id).await?;
+    Ok(HttpResponse::Created().json(entry))
+}
+
+pub async fn get_waitlist_position(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, student_id) = params.into_inner();
+    app_state.courses.get_course(tutor_id, course_id).await?;
+    let entry = enrollment::get_waitlist_entry(&app_state.db, course_id, student_id).await?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+pub async fn leave_course_waitlist(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id, student_id) = params.into_inner();
+    require_role(&app_state, tutor_id, course_id, &[OWNER, CO_TUTOR]).await?;
+    let entry = enrollment::leave_waitlist(&app_state.db, course_id, student_id).await?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::course::CreateCourseDto;
+    use crate::models::enrollment::WaitlistEntry;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn freed_seat_is_offered_to_waitlist() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+            name: "Some course with one seat".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+        let first_student = Uuid::new_v4();
+        let second_student = Uuid::new_v4();
+        let course_path = || web::Path::from((course.tutor_id, course.id));
+        let student_path = |student_id| web::Path::from((course.tutor_id, course.id, student_id));
+
+        let resp = set_course_capacity(app_state.clone(), web::Json(SetCapacityDto { capacity: 1 }), course_path()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = enroll_student(app_state.clone(), web::Json(StudentDto { student_id: first_student }), course_path()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = enroll_student(app_state.clone(), web::Json(StudentDto { student_id: second_student }), course_path()).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::CONFLICT);
+
+        let resp = join_course_waitlist(app_state.clone(), web::Json(StudentDto { student_id: second_student }), course_path()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let entry: WaitlistEntry = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(entry.position, Some(1));
+
+        let resp = withdraw_student(app_state.clone(), student_path(first_student)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = get_waitlist_position(app_state.clone(), student_path(second_student)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let entry: WaitlistEntry = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(entry.status, "offered");
+        assert!(entry.hold_expires_at.is_some());
+
+        let resp = enroll_student(app_state.clone(), web::Json(StudentDto { student_id: second_student }), course_path()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = get_waitlist_position(app_state.clone(), student_path(second_student)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,5 +1,7 @@
 use super::super::state::AppState;
 use actix_web::{HttpResponse, web};
+use actix_web::http::header::{ETag, LOCATION, VARY};
+use serde::Serialize;
 
 pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpResponse {
     let health_check_response = &app_state.health_check_response;
@@ -9,3 +11,15 @@ pub async fn health_check_handler(app_state: web::Data<AppState>) -> HttpRespons
     *visit_count += 1;
     HttpResponse::Ok().json(&response)
 }
+
+/// Answers a lookup by slug: the resource itself, tagged with `etag`, when `requested` is
+/// its current slug, or a permanent redirect to the current slug when it was looked up by one it
+/// had before a rename.
+pub fn slug_response<T: Serialize>(requested: &str, current: &str, etag: ETag, body: T) -> HttpResponse {
+    if requested == current {
+        HttpResponse::Ok().insert_header((VARY, "Accept-Language")).insert_header(etag).json(body)
+    } else {
+        // Relative to the requested URL, so it works under any scope the routes are mounted on.
+        HttpResponse::MovedPermanently().insert_header((LOCATION, current)).finish()
+    }
+}
@@ -0,0 +1,90 @@
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::models::learning_path::LearningPathDto;
+use crate::services;
+
+pub async fn new_path(app_state: web::Data<AppState>, path_dto: web::Json<LearningPathDto>) -> Result<HttpResponse, EzyTutorError> {
+    services::learning_path_service::create_path(app_state, path_dto.into()).await
+        .map(|path| HttpResponse::Created().json(path))
+}
+
+pub async fn get_paths(app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
+    services::learning_path_service::get_paths(app_state).await.map(|paths| HttpResponse::Ok().json(paths))
+}
+
+pub async fn get_path_by_id(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::learning_path_service::get_by_id(app_state, params.into_inner()).await.map(|path| HttpResponse::Ok().json(path))
+}
+
+pub async fn update_path(app_state: web::Data<AppState>, path_dto: web::Json<LearningPathDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::learning_path_service::update(app_state, path_dto.into(), params.into_inner()).await
+        .map(|path| HttpResponse::Ok().json(path))
+}
+
+pub async fn soft_delete_path(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::learning_path_service::soft_delete(app_state, params.into_inner()).await.map(|path| HttpResponse::Ok().json(path))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::course::CreateCourseDto;
+    use crate::models::learning_path::LearningPathDetail;
+    use crate::precondition::ExpectedVersion;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn path_hides_soft_deleted_courses() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let mut course_ids = vec![];
+        for tutor_id in ["d709c2c9-eeb8-4b6b-a63d-25ef38c78e61", "d709c2c9-eeb8-4b6b-a63d-25ef38c78e62"] {
+            let course = app_state.courses.new_course(CreateCourseDto {
+                tutor_id: Uuid::from_str(tutor_id).unwrap(),
+                name: "Some path course".into(),
+                description: None,
+                format: None,
+                structure: None,
+                duration: None,
+                price: None,
+                language: None,
+                level: None,
+            }).await.unwrap();
+            course_ids.push(course.id);
+        }
+
+        let path_dto = web::Json(LearningPathDto {
+            title: "Some learning path".into(),
+            description: None,
+            price: Some(120),
+            course_ids: vec![course_ids[1], course_ids[0]],
+        });
+        let resp = new_path(app_state.clone(), path_dto).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let path: LearningPathDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let ordered: Vec<Uuid> = path.courses.iter().map(|course| course.id).collect();
+        assert_eq!(ordered, vec![course_ids[1], course_ids[0]]);
+
+        let deleted = app_state.courses.get_course(Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e62").unwrap(), course_ids[1]).await.unwrap();
+        app_state.courses.soft_delete_course(deleted.tutor_id, deleted.id, &ExpectedVersion::Any).await.unwrap();
+
+        let resp = get_path_by_id(app_state.clone(), web::Path::from(path.path.id)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let path: LearningPathDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let ordered: Vec<Uuid> = path.courses.iter().map(|course| course.id).collect();
+        assert_eq!(ordered, vec![course_ids[0]]);
+    }
+}
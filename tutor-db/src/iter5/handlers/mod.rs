@@ -1,3 +1,12 @@
+pub mod booking;
+pub mod coupon;
 pub mod course;
+pub mod course_tag;
+pub mod course_translation;
+pub mod course_tutor;
+pub mod credential;
+pub mod enrollment;
+pub mod learning_path;
+pub mod recommendation;
 pub mod tutor;
 pub mod general;
\ No newline at end of file
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use super::super::errors::EzyTutorError;
+use super::super::state::AppState;
+use actix_web::{HttpResponse, web};
+use uuid::Uuid;
+use crate::dbaccess::course_tag;
+use crate::models::course::{Course, SimilarCourse, SimilarCoursesQuery};
+
+const DEFAULT_SIMILAR_COURSES: i64 = 5;
+const MAX_SIMILAR_COURSES: i64 = 20;
+
+pub async fn get_similar_courses(app_state: web::Data<AppState>, params: web::Path<(Uuid, Uuid)>, query: web::Query<SimilarCoursesQuery>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, course_id) = params.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_SIMILAR_COURSES).clamp(1, MAX_SIMILAR_COURSES);
+    app_state.courses.get_course(tutor_id, course_id).await?;
+
+    let scores = course_tag::get_similar_course_scores(&app_state.db, course_id, limit).await?;
+    let course_ids: Vec<Uuid> = scores.iter().map(|(id, _)| *id).collect();
+    let mut courses: HashMap<Uuid, Course> = app_state.courses.get_courses_by_ids(&course_ids).await?
+        .into_iter()
+        .map(|course| (course.id, course))
+        .collect();
+
+    let similar: Vec<SimilarCourse> = scores.into_iter()
+        .filter_map(|(id, score)| courses.remove(&id).map(|course| SimilarCourse { course, score }))
+        .collect();
+    Ok(HttpResponse::Ok().json(similar))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::models::course::CreateCourseDto;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn most_similar_course_comes_first() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let marker = Uuid::new_v4().simple().to_string();
+        let mut courses = vec![];
+        for name in ["Zymurgy fermentation basics", "Zymurgy fermentation advanced"] {
+            let course = app_state.courses.new_course(CreateCourseDto {
+                tutor_id: Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap(),
+                name: format!("{} {}", name, marker),
+                description: Some("Brewing with wild yeasts".into()),
+                format: None,
+                structure: None,
+                duration: None,
+                price: None,
+                language: Some(marker[..20].to_string()),
+                level: Some("Level 1".into()),
+            }).await.unwrap();
+            course_tag::replace_tags(&app_state.db, course.id, std::slice::from_ref(&marker)).await.unwrap();
+            courses.push(course);
+        }
+
+        let params = web::Path::from((courses[0].tutor_id, courses[0].id));
+        let resp = get_similar_courses(app_state, params, web::Query(SimilarCoursesQuery { limit: Some(3) })).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let similar: Vec<SimilarCourse> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert!(similar.len() <= 3);
+        assert_eq!(similar[0].course.id, courses[1].id);
+    }
+}
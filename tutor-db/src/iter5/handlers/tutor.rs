@@ -1,34 +1,423 @@
 use super::super::errors::EzyTutorError;
 use super::super::
 state::AppState;
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::header;
+use actix_web::http::header::{AcceptLanguage, IfMatch, VARY};
+use json_patch::Patch;
 use uuid::Uuid;
-use crate::models::tutor::{CreateTutorDto, PatchTutorDto};
+use crate::models::duplicate::{CreateTutorQuery, MergeTutorDto};
+use crate::models::include::{IncludeQuery, COURSES};
+use crate::models::stats::StatsQuery;
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, RateTutorDto, TutorListQuery};
+use crate::handlers::general::slug_response;
+use crate::i18n;
+use crate::precondition::{etag, representation_etag, ExpectedVersion};
+use crate::replica::Consistency;
 use crate::services;
 
-pub async fn new_tutor(app_state: web::Data<AppState>, course_dto: web::Json<CreateTutorDto>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::create_tutor(app_state, course_dto.into()).await
+pub async fn new_tutor(app_state: web::Data<AppState>, course_dto: web::Json<CreateTutorDto>, query: web::Query<CreateTutorQuery>) -> Result<HttpResponse, EzyTutorError> {
+    services::tutor_service::create_tutor(app_state, course_dto.into(), query.allow_duplicate.unwrap_or(false)).await
         .map(|tutor| HttpResponse::Created().json(tutor))
 }
 
-pub async fn get_tutors(app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::get_tutors(app_state).await.map(|tutors| HttpResponse::Ok().json(tutors))
+/// Admin report of the tutors that may have been created twice.
+pub async fn get_possible_duplicates(app_state: web::Data<AppState>) -> Result<HttpResponse, EzyTutorError> {
+    services::tutor_service::get_possible_duplicates(app_state).await
+        .map(|duplicates| HttpResponse::Ok().json(duplicates))
 }
 
-pub async fn get_tutor_by_id(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::get_by_id(app_state, params.into_inner()).await.map(|tutors| HttpResponse::Ok().json(tutors))
+pub async fn merge_tutor(app_state: web::Data<AppState>, merge_dto: web::Json<MergeTutorDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
+    services::tutor_service::merge(app_state, params.into_inner(), merge_dto.into()).await
+        .map(|merge| HttpResponse::Ok().json(merge))
 }
 
-pub async fn update_tutor_detail(app_state: web::Data<AppState>, course_dto: web::Json<CreateTutorDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::update(app_state, course_dto.into(), params.into_inner()).await
-        .map(|tutors| HttpResponse::Ok().json(tutors))
+/// Lists a page of tutors, with `Link` headers pointing at the first and next pages.
+pub async fn get_tutors(app_state: web::Data<AppState>, req: HttpRequest, query: web::Query<TutorListQuery>, accept_language: Option<web::Header<AcceptLanguage>>, consistency: Consistency) -> Result<HttpResponse, EzyTutorError> {
+    let query = query.into_inner();
+    let include = IncludeQuery { include: query.include.clone() };
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let page = services::tutor_service::get_tutors(app_state.clone(), &query, consistency).await?;
+    let tutors = services::tutor_service::with_courses(&app_state, page.tutors, &include, &locales).await?;
+
+    let page_link = |after: Option<String>, rel: &str| {
+        let query = serde_urlencoded::to_string(TutorListQuery { after, ..query.clone() }).unwrap_or_default();
+        format!("<{}?{}>; rel=\"{}\"", req.path(), query, rel)
+    };
+    let mut links = vec![page_link(None, "first")];
+    if let Some(next) = page.next {
+        links.push(page_link(Some(next.encode()), "next"));
+    }
+    Ok(HttpResponse::Ok()
+        .insert_header((header::LINK, links.join(", ")))
+        .insert_header((VARY, "Accept-Language"))
+        .json(tutors))
+}
+
+pub async fn get_tutor_by_id(app_state: web::Data<AppState>, params: web::Path<Uuid>, include: web::Query<IncludeQuery>, accept_language: Option<web::Header<AcceptLanguage>>) -> Result<HttpResponse, EzyTutorError> {
+    let tutor = services::tutor_service::get_by_id(app_state.clone(), params.into_inner()).await?;
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let etag = representation_etag(tutor.version, &locales, &include.included(&[COURSES])?);
+    let tutor = services::tutor_service::detail(&app_state, tutor, &include, &locales).await?;
+    Ok(HttpResponse::Ok().insert_header((VARY, "Accept-Language")).insert_header(etag).json(tutor))
+}
+
+pub async fn get_tutor_by_slug(app_state: web::Data<AppState>, params: web::Path<String>, include: web::Query<IncludeQuery>, accept_language: Option<web::Header<AcceptLanguage>>) -> Result<HttpResponse, EzyTutorError> {
+    let slug = params.into_inner();
+    let tutor = services::tutor_service::get_by_slug(app_state.clone(), &slug).await?;
+    let locales = i18n::requested_locales(accept_language.as_deref());
+    let current = tutor.slug.clone();
+    let etag = representation_etag(tutor.version, &locales, &include.included(&[COURSES])?);
+    let tutor = services::tutor_service::detail(&app_state, tutor, &include, &locales).await?;
+    Ok(slug_response(&slug, &current, etag, tutor))
+}
+
+pub async fn update_tutor_detail(app_state: web::Data<AppState>, course_dto: web::Json<CreateTutorDto>, params: web::Path<Uuid>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    services::tutor_service::update(app_state, course_dto.into(), params.into_inner(), &expected).await
+        .map(|tutor| HttpResponse::Ok().insert_header(etag(tutor.version)).json(tutor))
+}
+
+pub async fn partially_update_tutor_detail(app_state: web::Data<AppState>, course_dto: web::Json<PatchTutorDto>, params: web::Path<Uuid>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    services::tutor_service::partial_update(app_state, course_dto.into(), params.into_inner(), &expected).await
+        .map(|tutor| HttpResponse::Ok().insert_header(etag(tutor.version)).json(tutor))
+}
+
+pub async fn json_patch_tutor_detail(app_state: web::Data<AppState>, patch: web::Json<Patch>, params: web::Path<Uuid>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    services::tutor_service::json_patch(app_state, patch.into_inner(), params.into_inner(), &expected).await
+        .map(|tutor| HttpResponse::Ok().insert_header(etag(tutor.version)).json(tutor))
+}
+
+pub async fn soft_delete_tutor(app_state: web::Data<AppState>, params: web::Path<Uuid>, if_match: Option<web::Header<IfMatch>>) -> Result<HttpResponse, EzyTutorError> {
+    let expected = ExpectedVersion::from_header(if_match, app_state.require_if_match)?;
+    services::tutor_service::soft_delete(app_state, params.into_inner(), &expected).await.map(|tutors| HttpResponse::Ok().json(tutors))
 }
 
-pub async fn partially_update_tutor_detail(app_state: web::Data<AppState>, course_dto: web::Json<PatchTutorDto>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::partial_update(app_state, course_dto.into(), params.into_inner()).await
-        .map(|tutors| HttpResponse::Ok().json(tutors))
+pub async fn get_tutor_stats(app_state: web::Data<AppState>, params: web::Path<Uuid>, query: web::Query<StatsQuery>) -> Result<HttpResponse, EzyTutorError> {
+    services::tutor_service::stats(app_state, params.into_inner(), query.into_inner()).await
+        .map(|stats| HttpResponse::Ok().json(stats))
 }
 
-pub async fn soft_delete_tutor(app_state: web::Data<AppState>, params: web::Path<Uuid>) -> Result<HttpResponse, EzyTutorError> {
-    services::tutor_service::soft_delete(app_state, params.into_inner()).await.map(|tutors| HttpResponse::Ok().json(tutors))
+pub async fn rate_tutor(app_state: web::Data<AppState>, rating_dto: web::Json<RateTutorDto>, params: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, EzyTutorError> {
+    let (tutor_id, student_id) = params.into_inner();
+    services::tutor_service::rate(app_state, tutor_id, student_id, rating_dto.into()).await
+        .map(|rating| HttpResponse::Ok().json(rating))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use actix_web::body::MessageBody;
+    use actix_web::http::header::QualityItem;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use actix_web::ResponseError;
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use crate::dbaccess;
+    use crate::models::coupon::{CreateCouponDto, PERCENTAGE_DISCOUNT};
+    use crate::models::course::{CreateCourseDto, UpsertCourseTranslationDto};
+    use crate::models::credential::{APPROVED, CredentialDto, DEGREE};
+    use crate::models::duplicate::{PossibleDuplicate, TutorMerge};
+    use crate::models::stats::{StatsBucket, TutorStats};
+    use crate::models::tutor::{Tutor, TutorDetail, TutorSort};
+    use super::*;
+
+    #[actix_rt::test]
+    async fn tutors_are_paginated_by_name() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+
+        let prefix = format!("Paging {}", Uuid::new_v4().simple());
+        for suffix in ["c", "A", "b"] {
+            services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+                name: format!("{} {}", prefix, suffix),
+                pic_url: "https://i.pravatar.cc/150".into(),
+                profile: "Some profile".into(),
+            }, true).await.unwrap();
+        }
+
+        let query = TutorListQuery { sort: Some(TutorSort::Name), name: Some(prefix.to_lowercase()), limit: Some(2), ..Default::default() };
+        let req = TestRequest::get().uri("/api/v1/tutors/").to_http_request();
+        let resp = get_tutors(app_state.clone(), req, web::Query(query.clone()), None, Consistency::ReadYourWrites).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let link = resp.headers().get(header::LINK).unwrap().to_str().unwrap().to_string();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let first_page: Vec<Tutor> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let names: Vec<String> = first_page.iter().map(|tutor| tutor.name.clone()).collect();
+        assert_eq!(names, vec![format!("{} A", prefix), format!("{} b", prefix)]);
+        let after = link.split("after=").nth(1).and_then(|rest| rest.split('>').next()).unwrap().to_string();
+        assert!(link.ends_with("rel=\"next\""));
+
+        // Renaming the last tutor of the page does not move the next one
+        let last = &first_page[1];
+        app_state.tutors.update(CreateTutorDto { name: format!("{} 0", prefix), pic_url: last.pic_url.clone(), profile: last.profile.clone() },
+                                last.id, &ExpectedVersion::Any).await.unwrap();
+
+        let req = TestRequest::get().uri("/api/v1/tutors/").to_http_request();
+        let resp = get_tutors(app_state.clone(), req, web::Query(TutorListQuery { after: Some(after), ..query.clone() }), None, Consistency::ReadYourWrites).await.unwrap();
+        let link = resp.headers().get(header::LINK).unwrap().to_str().unwrap().to_string();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let second_page: Vec<Tutor> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let names: Vec<String> = second_page.iter().map(|tutor| tutor.name.clone()).collect();
+        assert_eq!(names, vec![format!("{} c", prefix)]);
+        assert!(!link.contains("rel=\"next\""));
+
+        let req = TestRequest::get().uri("/api/v1/tutors/").to_http_request();
+        let resp = get_tutors(app_state.clone(), req, web::Query(TutorListQuery { after: Some("not-a-cursor".into()), ..query }), None, Consistency::ReadYourWrites).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn merge_patch_tells_absent_from_null() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: "Some patched tutor".into(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+
+        let patch: PatchTutorDto = serde_json::from_str(r#"{"profile": "Some other profile"}"#).unwrap();
+        let resp = partially_update_tutor_detail(app_state.clone(), web::Json(patch), web::Path::from(tutor.id), None).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let patched: Tutor = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(patched.name, tutor.name);
+        assert_eq!(patched.profile, "Some other profile");
+        assert!(patched.updated_at > tutor.updated_at);
+
+        let patch: PatchTutorDto = serde_json::from_str(r#"{"name": null}"#).unwrap();
+        let resp = partially_update_tutor_detail(app_state.clone(), web::Json(patch), web::Path::from(tutor.id), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn tutor_embeds_courses_on_request() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: "Some embedding tutor".into(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: tutor.id,
+            name: "Some embedded course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+
+        let tutor_id = tutor.id;
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(tutor_id), web::Query(IncludeQuery::default()), None).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let tutor: TutorDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(tutor.courses, None);
+
+        let include = IncludeQuery { include: Some(" ,".into()) };
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(tutor_id), web::Query(include), None).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let tutor: TutorDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(tutor.courses, None);
+
+        let include = IncludeQuery { include: Some("courses,".into()) };
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(tutor_id), web::Query(include), None).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let tutor: TutorDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(tutor.courses, Some(vec![course]));
+
+        let include = IncludeQuery { include: Some("tutor".into()) };
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(tutor_id), web::Query(include), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn embedded_courses_are_localized() {
+        let app_state: web::Data<AppState> = web::Data::new(AppState::in_memory());
+
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: format!("Localized {}", Uuid::new_v4().simple()),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: tutor.id,
+            name: "Some embedded course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+
+        app_state.courses.upsert_translation(course.id, "en".into(), UpsertCourseTranslationDto {
+            name: "Some translated course".into(),
+            description: Some("Some *translated* description".into()),
+        }).await.unwrap();
+        let include = IncludeQuery { include: Some("courses".into()) };
+        let accept_language = web::Header(AcceptLanguage(vec![QualityItem::max("en-US".parse().unwrap())]));
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(tutor.id), web::Query(include), Some(accept_language)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let tutor: TutorDetail = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let courses = tutor.courses.unwrap();
+        assert_eq!(courses[0].name, "Some translated course");
+        assert_eq!(courses[0].description_html.as_deref(), Some("<p>Some <em>translated</em> description</p>\n"));
+    }
+
+    #[actix_rt::test]
+    async fn stats_aggregate_courses_enrollments_and_ratings() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let tutor = services::tutor_service::create_tutor(app_state.clone(), CreateTutorDto {
+            name: format!("Stats {}", Uuid::new_v4().simple()),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }, true).await.unwrap();
+        let mut course_ids = vec![];
+        for price in [100, 300, 500] {
+            let course = app_state.courses.new_course(CreateCourseDto {
+                tutor_id: tutor.id,
+                name: "Some stats course".into(),
+                description: None,
+                format: None,
+                structure: None,
+                duration: None,
+                price: Some(price),
+                language: None,
+                level: None,
+            }).await.unwrap();
+            course_ids.push(course.id);
+        }
+        app_state.courses.soft_delete_course(tutor.id, course_ids[2], &ExpectedVersion::Any).await.unwrap();
+        dbaccess::enrollment::enroll(&app_state.db, course_ids[0], Uuid::new_v4()).await.unwrap();
+        dbaccess::enrollment::enroll(&app_state.db, course_ids[1], Uuid::new_v4()).await.unwrap();
+        app_state.tutors.rate(tutor.id, Uuid::new_v4(), 4).await.unwrap();
+        app_state.tutors.rate(tutor.id, Uuid::new_v4(), 5).await.unwrap();
+
+        let query = StatsQuery { bucket: Some(StatsBucket::Week), buckets: Some(4) };
+        let resp = get_tutor_stats(app_state.clone(), web::Path::from(tutor.id), web::Query(query)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let stats: TutorStats = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!((stats.active_courses, stats.deleted_courses), (2, 1));
+        assert_eq!((stats.min_price, stats.max_price, stats.average_price), (Some(100), Some(300), Some(200.0)));
+        assert_eq!(stats.total_enrollments, 2);
+        assert_eq!((stats.ratings, stats.average_rating), (2, Some(4.5)));
+        assert_eq!(stats.activity.len(), 4);
+        let current = stats.activity.last().unwrap();
+        assert_eq!((current.courses_created, current.enrollments), (3, 2));
+
+        let query = StatsQuery { bucket: None, buckets: Some(0) };
+        let resp = get_tutor_stats(app_state.clone(), web::Path::from(tutor.id), web::Query(query)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn duplicate_is_refused_reported_and_merged() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let db_pool = PgPool::connect(&database_url).await.unwrap();
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState::new("".to_string(), db_pool));
+
+        let name = format!("Hypatia {}", Uuid::new_v4().simple());
+        let tutor_dto = CreateTutorDto {
+            name: name.clone(),
+            pic_url: format!("https://i.pravatar.cc/150?u={}", Uuid::new_v4()),
+            profile: "Some profile".into(),
+        };
+        let resp = new_tutor(app_state.clone(), web::Json(tutor_dto.clone()), web::Query(CreateTutorQuery::default())).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let original: Tutor = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+
+        let lookalike = CreateTutorDto { name: format!("{} ", name.to_uppercase()), ..tutor_dto.clone() };
+        let resp = new_tutor(app_state.clone(), web::Json(lookalike.clone()), web::Query(CreateTutorQuery::default())).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::CONFLICT);
+        let query = CreateTutorQuery { allow_duplicate: Some(true) };
+        let resp = new_tutor(app_state.clone(), web::Json(lookalike), web::Query(query)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let duplicate: Tutor = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        // The same picture alone, as with a placeholder avatar, is not a duplicate
+        let stranger = CreateTutorDto { name: format!("Zenobia {}", Uuid::new_v4().simple()), ..tutor_dto.clone() };
+        let resp = new_tutor(app_state.clone(), web::Json(stranger), web::Query(CreateTutorQuery::default())).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = get_possible_duplicates(app_state.clone()).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let report: Vec<PossibleDuplicate> = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        let pair = report.iter().find(|pair| pair.duplicate_id == duplicate.id).unwrap();
+        assert_eq!(pair.tutor_id, original.id);
+        assert!(pair.same_pic_url);
+
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: duplicate.id,
+            name: "Some duplicated course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+        let coupon = |code: &str| CreateCouponDto {
+            code: code.into(),
+            discount_type: PERCENTAGE_DISCOUNT.into(),
+            amount: 10,
+            expires_at: None,
+            max_redemptions: None,
+            course_ids: vec![],
+        };
+        dbaccess::coupon::create(&app_state.db, original.id, coupon("WELCOME")).await.unwrap();
+        let clashing = dbaccess::coupon::create(&app_state.db, duplicate.id, coupon("WELCOME")).await.unwrap();
+        let merge_dto = web::Json(MergeTutorDto { duplicate_id: duplicate.id });
+        let resp = merge_tutor(app_state.clone(), merge_dto, web::Path::from(original.id)).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::CONFLICT);
+        dbaccess::coupon::soft_delete(&app_state.db, duplicate.id, clashing.id).await.unwrap();
+
+        let spring = dbaccess::coupon::create(&app_state.db, duplicate.id, coupon("SPRING")).await.unwrap();
+        let credential = dbaccess::credential::add_credential(&app_state.db, duplicate.id, CredentialDto {
+            kind: DEGREE.into(),
+            title: "Some degree".into(),
+            issuer: "Some university".into(),
+            issued_on: None,
+            attachment_urls: vec![],
+        }).await.unwrap();
+        dbaccess::credential::review_credential(&app_state.db, duplicate.id, credential.id, APPROVED, None).await.unwrap();
+        let student_id = Uuid::new_v4();
+        dbaccess::tutor::rate(&app_state.db, original.id, student_id, 2).await.unwrap();
+        dbaccess::tutor::rate(&app_state.db, duplicate.id, student_id, 5).await.unwrap();
+        dbaccess::tutor::rate(&app_state.db, duplicate.id, Uuid::new_v4(), 4).await.unwrap();
+
+        let merge_dto = web::Json(MergeTutorDto { duplicate_id: duplicate.id });
+        let resp = merge_tutor(app_state.clone(), merge_dto, web::Path::from(original.id)).await.unwrap();
+        let body = &resp.into_body().try_into_bytes().unwrap()[..];
+        let merge: TutorMerge = serde_json::from_str(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(merge.moved_courses, vec![course.id]);
+        assert!(merge.tutor.verified);
+        assert!(!merge.duplicate.verified);
+        let coupons = dbaccess::coupon::get_coupons_by_tutor(&app_state.db, original.id).await.unwrap();
+        assert!(coupons.iter().any(|coupon| coupon.id == spring.id));
+        let credentials = dbaccess::credential::get_credentials(&app_state.db, original.id).await.unwrap();
+        assert_eq!(credentials.iter().map(|credential| credential.id).collect::<Vec<_>>(), vec![credential.id]);
+        let stats = dbaccess::stats::get_tutor_stats(&app_state.db, original.id).await.unwrap();
+        assert_eq!((stats.ratings, stats.average_rating), (2, Some(4.5)));
+        let moved = app_state.courses.get_course(original.id, course.id).await.unwrap();
+        assert_eq!(moved.tutor_id, original.id);
+        let resp = get_tutor_by_id(app_state.clone(), web::Path::from(duplicate.id), web::Query(IncludeQuery::default()), None).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,40 @@
+use actix_web::http::header::{AcceptLanguage, Preference};
+use crate::errors::EzyTutorError;
+
+/// Locales requested through `Accept-Language`, most preferred first. Every regional tag is
+/// followed by its primary language (`es-ar` then `es`) so it can fall back to a broader
+/// translation before falling back to the course default content.
+pub fn requested_locales(accept_language: Option<&AcceptLanguage>) -> Vec<String> {
+    let mut locales: Vec<String> = vec![];
+    let Some(accept_language) = accept_language else {
+        return locales;
+    };
+
+    for preference in accept_language.ranked() {
+        if let Preference::Specific(tag) = preference {
+            for locale in [tag.as_str().to_lowercase(), tag.primary_language().to_lowercase()] {
+                if !locales.contains(&locale) {
+                    locales.push(locale);
+                }
+            }
+        }
+    }
+    locales
+}
+
+/// Validates a BCP 47 like locale (`en`, `es-AR`, `zh-Hant-TW`) and returns it in the
+/// lowercase form translations are stored with.
+pub fn normalize_locale(locale: &str) -> Result<String, EzyTutorError> {
+    let mut subtags = locale.split('-');
+    let primary_is_valid = subtags.next()
+        .map(|primary| (2..=8).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic()))
+        .unwrap_or(false);
+    let rest_is_valid = subtags
+        .all(|subtag| (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    if primary_is_valid && rest_is_valid && locale.len() <= 35 {
+        Ok(locale.to_lowercase())
+    } else {
+        Err(EzyTutorError::InvalidInput(format!("Invalid locale: {}", locale)))
+    }
+}
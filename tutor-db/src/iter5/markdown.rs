@@ -0,0 +1,24 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders Markdown written by tutors to HTML that is safe to embed in our pages: raw HTML is
+/// parsed too, and everything outside ammonia's allow-list (scripts, event handlers,
+/// `javascript:` links...) is stripped. Links get `rel="noopener noreferrer"`.
+pub fn to_html(source: &str) -> String {
+    let parser = Parser::new_ext(source, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES);
+    let mut unsafe_html = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, parser);
+    ammonia::clean(&unsafe_html)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders_and_sanitizes() {
+        assert_eq!(to_html("# Óptica\n\n- lentes\n- espejos"), "<h1>Óptica</h1>\n<ul>\n<li>lentes</li>\n<li>espejos</li>\n</ul>\n");
+        assert_eq!(to_html("[blog](https://example.com)"), "<p><a href=\"https://example.com\" rel=\"noopener noreferrer\">blog</a></p>\n");
+        let html = to_html("<script>alert(1)</script>\n\n[x](javascript:alert(1)) <img src=x onerror=alert(1)>");
+        assert!(!html.contains("script") && !html.contains("onerror"), "{}", html);
+    }
+}
@@ -0,0 +1,20 @@
+drop function if exists tutor_availability;
+drop table if exists lessons;
+drop table if exists availability_exceptions;
+drop table if exists availability_rules;
+drop table if exists slugs;
+drop table if exists tutor_credentials;
+drop table if exists tutor_ratings;
+drop table if exists course_tutors;
+drop table if exists course_tags;
+drop table if exists learning_path_courses;
+drop table if exists learning_paths;
+drop table if exists coupon_redemptions;
+drop table if exists coupon_courses;
+drop table if exists coupons;
+drop table if exists waitlist_entries;
+drop table if exists enrollments;
+drop table if exists course_seats;
+drop table if exists course_translations;
+drop table if exists ezy_course_c4;
+drop table if exists tutors;
@@ -0,0 +1,43 @@
+drop trigger ezy_course_c4_updated_at on ezy_course_c4;
+drop trigger tutors_updated_at on tutors;
+drop function set_updated_at;
+
+drop index tutors_deleted_at;
+drop index ezy_course_c4_tutor_id_deleted_at;
+
+-- As in the up migration, the foreign keys are dropped while the constraints change.
+create temporary table referencing_keys on commit drop as
+select conrelid::regclass as referencing, conname, pg_get_constraintdef(oid) as definition
+from pg_constraint
+where contype = 'f' and confrelid in ('tutors'::regclass, 'ezy_course_c4'::regclass);
+
+do
+$$
+DECLARE
+    fk record;
+BEGIN
+    FOR fk IN SELECT * FROM referencing_keys LOOP
+        EXECUTE format('alter table %s drop constraint %I', fk.referencing, fk.conname);
+    END LOOP;
+END
+$$;
+
+alter table ezy_course_c4 drop constraint ezy_course_c4_pkey;
+alter table ezy_course_c4 add constraint ezy_course_c4_id_key unique (id);
+alter table ezy_course_c4 add constraint ezy_course_c4_pkey primary key (id, created_at, updated_at);
+alter table ezy_course_c4 add unique (id, tutor_id, created_at, updated_at);
+
+alter table tutors drop constraint tutors_pkey;
+alter table tutors add constraint tutors_id_key unique (id);
+alter table tutors add constraint tutors_pkey primary key (id, created_at, updated_at);
+
+do
+$$
+DECLARE
+    fk record;
+BEGIN
+    FOR fk IN SELECT * FROM referencing_keys LOOP
+        EXECUTE format('alter table %s add constraint %I %s', fk.referencing, fk.conname, fk.definition);
+    END LOOP;
+END
+$$;
@@ -0,0 +1,59 @@
+-- The primary keys included created_at and updated_at, so they changed on every update and
+-- foreign keys had to rely on the separate UNIQUE (id). The foreign keys depend on that
+-- constraint, so they are dropped while it becomes the primary key, then recreated as they were.
+create temporary table referencing_keys on commit drop as
+select conrelid::regclass as referencing, conname, pg_get_constraintdef(oid) as definition
+from pg_constraint
+where contype = 'f' and confrelid in ('tutors'::regclass, 'ezy_course_c4'::regclass);
+
+do
+$$
+DECLARE
+    fk record;
+BEGIN
+    FOR fk IN SELECT * FROM referencing_keys LOOP
+        EXECUTE format('alter table %s drop constraint %I', fk.referencing, fk.conname);
+    END LOOP;
+END
+$$;
+
+alter table tutors drop constraint tutors_pkey;
+alter table tutors drop constraint tutors_id_key;
+alter table tutors add constraint tutors_pkey primary key (id);
+
+alter table ezy_course_c4 drop constraint ezy_course_c4_pkey;
+alter table ezy_course_c4 drop constraint ezy_course_c4_id_key;
+alter table ezy_course_c4 drop constraint ezy_course_c4_id_tutor_id_created_at_updated_at_key;
+alter table ezy_course_c4 add constraint ezy_course_c4_pkey primary key (id);
+
+do
+$$
+DECLARE
+    fk record;
+BEGIN
+    FOR fk IN SELECT * FROM referencing_keys LOOP
+        EXECUTE format('alter table %s add constraint %I %s', fk.referencing, fk.conname, fk.definition);
+    END LOOP;
+END
+$$;
+
+-- Courses are almost always looked up by tutor, active or soft deleted.
+create index ezy_course_c4_tutor_id_deleted_at on ezy_course_c4 (tutor_id, deleted_at);
+create index tutors_deleted_at on tutors (deleted_at) where deleted_at is not null;
+
+-- updated_at is kept by the database, in UTC like the timestamps written by the application.
+create function set_updated_at() returns trigger
+    language plpgsql as
+$$
+BEGIN
+    NEW.updated_at = now() AT TIME ZONE 'utc';
+    RETURN NEW;
+END
+$$;
+
+create trigger tutors_updated_at
+    before update on tutors
+    for each row execute function set_updated_at();
+create trigger ezy_course_c4_updated_at
+    before update on ezy_course_c4
+    for each row execute function set_updated_at();
@@ -0,0 +1,6 @@
+drop trigger ezy_course_c4_version on ezy_course_c4;
+drop trigger tutors_version on tutors;
+drop function bump_version;
+
+alter table ezy_course_c4 drop column version;
+alter table tutors drop column version;
@@ -0,0 +1,21 @@
+-- Tutors and courses carry a version for optimistic concurrency. It is their ETag, and writes
+-- sent with an If-Match of an older version are refused.
+alter table tutors add column version integer NOT NULL default 1;
+alter table ezy_course_c4 add column version integer NOT NULL default 1;
+
+-- Every update of a row is a new version, whoever writes it.
+create function bump_version() returns trigger
+    language plpgsql as
+$$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END
+$$;
+
+create trigger tutors_version
+    before update on tutors
+    for each row execute function bump_version();
+create trigger ezy_course_c4_version
+    before update on ezy_course_c4
+    for each row execute function bump_version();
@@ -0,0 +1,168 @@
+use actix_web::web;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+
+pub const BOOKED: &str = "booked";
+pub const CANCELLED: &str = "cancelled";
+/// Lessons can be cancelled until this many hours before they start.
+pub const CANCELLATION_WINDOW_HOURS: i64 = 24;
+pub const DEFAULT_LESSON_MINUTES: i32 = 60;
+/// Longest date range the open slots can be computed for.
+pub const MAX_SLOT_RANGE_DAYS: i64 = 31;
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct AvailabilityRule {
+    pub id: Uuid,
+    pub tutor_id: Uuid,
+    /// ISO weekday, 1 is Monday and 7 is Sunday.
+    pub weekday: i16,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub timezone: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AvailabilityRuleDto {
+    pub weekday: i16,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+/// The whole weekly schedule of a tutor, as wall clock times in `timezone`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeeklyAvailabilityDto {
+    pub timezone: String,
+    pub rules: Vec<AvailabilityRuleDto>,
+}
+
+impl From<web::Json<WeeklyAvailabilityDto>> for WeeklyAvailabilityDto {
+    fn from(value: web::Json<WeeklyAvailabilityDto>) -> Self {
+        WeeklyAvailabilityDto {
+            timezone: value.timezone.clone(),
+            rules: value.rules.clone(),
+        }
+    }
+}
+
+impl WeeklyAvailabilityDto {
+    pub fn validate(&self) -> Result<(), EzyTutorError> {
+        for rule in &self.rules {
+            if !(1..=7).contains(&rule.weekday) {
+                return Err(EzyTutorError::InvalidInput("Weekday must be between 1 (Monday) and 7 (Sunday)".to_string()));
+            }
+            if rule.start_time >= rule.end_time {
+                return Err(EzyTutorError::InvalidInput("Availability must start before it ends".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct AvailabilityException {
+    pub id: Uuid,
+    pub tutor_id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Extra hours when true, time off when false.
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AvailabilityExceptionDto {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+impl From<web::Json<AvailabilityExceptionDto>> for AvailabilityExceptionDto {
+    fn from(value: web::Json<AvailabilityExceptionDto>) -> Self {
+        AvailabilityExceptionDto {
+            starts_at: value.starts_at,
+            ends_at: value.ends_at,
+            available: value.available,
+            reason: value.reason.clone(),
+        }
+    }
+}
+
+impl AvailabilityExceptionDto {
+    pub fn validate(&self) -> Result<(), EzyTutorError> {
+        if self.starts_at >= self.ends_at {
+            return Err(EzyTutorError::InvalidInput("Exception must start before it ends".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct Lesson {
+    pub id: Uuid,
+    pub tutor_id: Uuid,
+    pub student_id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub cancelled_at: Option<NaiveDateTime>,
+}
+
+impl Lesson {
+    pub fn can_be_cancelled(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at - now >= Duration::hours(CANCELLATION_WINDOW_HOURS)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BookLessonDto {
+    pub student_id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub duration_minutes: Option<i32>,
+}
+
+impl From<web::Json<BookLessonDto>> for BookLessonDto {
+    fn from(value: web::Json<BookLessonDto>) -> Self {
+        BookLessonDto {
+            student_id: value.student_id,
+            starts_at: value.starts_at,
+            duration_minutes: value.duration_minutes,
+        }
+    }
+}
+
+/// Query string of the open slots endpoint. `from` and `to` are days in the `tz` timezone,
+/// UTC when not given, and each slot lasts `duration` minutes.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SlotQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub tz: Option<String>,
+    pub duration: Option<i32>,
+}
+
+/// A bookable slot, in UTC and as wall clock times in the requested timezone.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OpenSlot {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub local_starts_at: NaiveDateTime,
+    pub local_ends_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OpenSlots {
+    pub timezone: String,
+    pub slots: Vec<OpenSlot>,
+}
+
+/// Lessons last between 15 minutes and 4 hours, in 15 minute steps.
+pub fn validate_lesson_minutes(minutes: i32) -> Result<i32, EzyTutorError> {
+    if !(15..=240).contains(&minutes) || minutes % 15 != 0 {
+        return Err(EzyTutorError::InvalidInput("Lessons last between 15 and 240 minutes, in steps of 15".to_string()));
+    }
+    Ok(minutes)
+}
@@ -0,0 +1,135 @@
+use actix_web::web;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+
+pub const PERCENTAGE_DISCOUNT: &str = "percentage";
+pub const FIXED_DISCOUNT: &str = "fixed";
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct Coupon {
+    pub id: Uuid,
+    pub tutor_id: Uuid,
+    pub code: String,
+    pub discount_type: String,
+    pub amount: i32,
+    pub expires_at: Option<NaiveDateTime>,
+    pub max_redemptions: Option<i32>,
+    pub redemptions: i32,
+    /// Courses the coupon is restricted to, empty when it applies to every course of the tutor.
+    pub course_ids: Vec<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Coupon {
+    pub fn applies_to(&self, course_id: Uuid) -> bool {
+        self.course_ids.is_empty() || self.course_ids.contains(&course_id)
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false)
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.max_redemptions.map(|max| self.redemptions >= max).unwrap_or(false)
+    }
+
+    /// Price after the discount, never below zero. Percentages round in favour of the student.
+    pub fn discounted_price(&self, price: i32) -> i32 {
+        let discounted = match self.discount_type.as_str() {
+            PERCENTAGE_DISCOUNT => (price as i64 * (100 - self.amount as i64) / 100) as i32,
+            _ => price - self.amount,
+        };
+        discounted.max(0)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CreateCouponDto {
+    pub code: String,
+    pub discount_type: String,
+    pub amount: i32,
+    pub expires_at: Option<NaiveDateTime>,
+    pub max_redemptions: Option<i32>,
+    #[serde(default)]
+    pub course_ids: Vec<Uuid>,
+}
+
+impl CreateCouponDto {
+    /// Checks the coupon rules and upper-cases the code, which is matched case-insensitively.
+    pub fn validate(mut self) -> Result<Self, EzyTutorError> {
+        self.code = self.code.trim().to_uppercase();
+        if self.code.is_empty() || self.code.len() > 40 {
+            return Err(EzyTutorError::InvalidInput("Coupon code must have between 1 and 40 characters".to_string()));
+        }
+        let amount_is_valid = match self.discount_type.as_str() {
+            PERCENTAGE_DISCOUNT => (1..=100).contains(&self.amount),
+            FIXED_DISCOUNT => self.amount > 0,
+            _ => return Err(EzyTutorError::InvalidInput("Discount type must be either percentage or fixed".to_string())),
+        };
+        if !amount_is_valid {
+            return Err(EzyTutorError::InvalidInput("Discount amount must be positive and at most 100 for percentages".to_string()));
+        }
+        if self.max_redemptions.map(|max| max <= 0).unwrap_or(false) {
+            return Err(EzyTutorError::InvalidInput("Max redemptions must be greater than 0".to_string()));
+        }
+        Ok(self)
+    }
+}
+
+impl From<web::Json<CreateCouponDto>> for CreateCouponDto {
+    fn from(value: web::Json<CreateCouponDto>) -> Self {
+        CreateCouponDto {
+            code: value.code.clone(),
+            discount_type: value.discount_type.clone(),
+            amount: value.amount,
+            expires_at: value.expires_at,
+            max_redemptions: value.max_redemptions,
+            course_ids: value.course_ids.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct QuoteQuery {
+    pub code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub course_id: Uuid,
+    pub code: Option<String>,
+    pub price: i32,
+    pub discount: i32,
+    pub effective_price: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RedeemCouponDto {
+    pub code: String,
+    pub student_id: Uuid,
+}
+
+impl From<web::Json<RedeemCouponDto>> for RedeemCouponDto {
+    fn from(value: web::Json<RedeemCouponDto>) -> Self {
+        RedeemCouponDto {
+            code: value.code.clone(),
+            student_id: value.student_id,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct CouponRedemption {
+    pub id: Uuid,
+    pub coupon_id: Uuid,
+    pub course_id: Uuid,
+    pub student_id: Uuid,
+    pub price: i32,
+    pub effective_price: i32,
+    pub created_at: NaiveDateTime,
+}
@@ -2,6 +2,9 @@ use actix_web::web;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::markdown;
+use crate::models::tutor::Tutor;
 
 #[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialOrd, PartialEq)]
 pub struct Course {
@@ -20,6 +23,20 @@ pub struct Course {
     pub updated_at: Option<NaiveDateTime>,
     #[serde(skip_serializing)]
     pub deleted_at: Option<NaiveDateTime>,
+    pub slug: String,
+    /// `description` rendered from Markdown and sanitized.
+    pub description_html: Option<String>,
+    /// Bumped by every update, the `ETag` of the course.
+    pub version: i32,
+}
+
+/// A course with its owner, when it was asked for with `?include=tutor`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct CourseDetail {
+    #[serde(flatten)]
+    pub course: Course,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tutor: Option<Tutor>,
 }
 
 impl From<web::Json<Course>> for Course {
@@ -39,6 +56,9 @@ impl From<web::Json<Course>> for Course {
             created_at: value.created_at,
             updated_at: value.updated_at,
             deleted_at: value.deleted_at,
+            slug: value.slug.clone(),
+            description_html: value.description_html.clone(),
+            version: value.version,
         }
     }
 }
@@ -115,4 +135,86 @@ impl From<web::Json<UpdateCourseDto>> for UpdateCourseDto {
             level: value.level.clone(),
         }
     }
-}
\ No newline at end of file
+}
+impl Course {
+    /// Overlays the first translation matching `locales` (in preference order) on top of the
+    /// course content, which is kept as is when no translation matches.
+    pub fn localize(mut self, translations: &[CourseTranslation], locales: &[String]) -> Course {
+        let translation = locales.iter().find_map(|locale| {
+            translations.iter().find(|t| t.course_id == self.id && &t.locale == locale)
+        });
+        if let Some(translation) = translation {
+            self.name = translation.name.clone();
+            self.description = translation.description.clone();
+            self.description_html = self.description.as_deref().map(markdown::to_html);
+        }
+        self
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct CourseTranslation {
+    pub course_id: Uuid,
+    pub locale: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpsertCourseTranslationDto {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl From<web::Json<UpsertCourseTranslationDto>> for UpsertCourseTranslationDto {
+    fn from(value: web::Json<UpsertCourseTranslationDto>) -> Self {
+        UpsertCourseTranslationDto {
+            name: value.name.clone(),
+            description: value.description.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CourseTagsDto {
+    pub tags: Vec<String>,
+}
+
+impl CourseTagsDto {
+    /// Tags are compared case-insensitively, so they are stored trimmed, lowercase and unique.
+    pub fn normalized(&self) -> Result<Vec<String>, EzyTutorError> {
+        let mut tags: Vec<String> = vec![];
+        for tag in &self.tags {
+            let tag = tag.trim().to_lowercase();
+            if tag.is_empty() || tag.chars().count() > 40 {
+                return Err(EzyTutorError::InvalidInput("Tags must have between 1 and 40 characters".to_string()));
+            }
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Ok(tags)
+    }
+}
+
+impl From<web::Json<CourseTagsDto>> for CourseTagsDto {
+    fn from(value: web::Json<CourseTagsDto>) -> Self {
+        CourseTagsDto {
+            tags: value.tags.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SimilarCoursesQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SimilarCourse {
+    #[serde(flatten)]
+    pub course: Course,
+    pub score: f64,
+}
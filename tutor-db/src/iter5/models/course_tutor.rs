@@ -0,0 +1,33 @@
+use actix_web::web;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Owns the course, the only role allowed to delete it or manage its tutors.
+pub const OWNER: &str = "owner";
+/// Co-teaches the course and can edit it.
+pub const CO_TUTOR: &str = "co_tutor";
+/// Helps with the course, which is listed under them, but can not edit it.
+pub const ASSISTANT: &str = "assistant";
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct CourseTutor {
+    pub course_id: Uuid,
+    pub tutor_id: Uuid,
+    pub role: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CourseTutorRoleDto {
+    pub role: String,
+}
+
+impl From<web::Json<CourseTutorRoleDto>> for CourseTutorRoleDto {
+    fn from(value: web::Json<CourseTutorRoleDto>) -> Self {
+        CourseTutorRoleDto {
+            role: value.role.clone(),
+        }
+    }
+}
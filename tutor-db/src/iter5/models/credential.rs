@@ -0,0 +1,96 @@
+use actix_web::web;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+
+pub const DEGREE: &str = "degree";
+pub const CERTIFICATE: &str = "certificate";
+
+/// Waiting for an admin to review it.
+pub const PENDING: &str = "pending";
+/// Reviewed and accepted, it makes the tutor verified.
+pub const APPROVED: &str = "approved";
+/// Reviewed and refused, always with a reason.
+pub const REJECTED: &str = "rejected";
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct Credential {
+    pub id: Uuid,
+    pub tutor_id: Uuid,
+    pub kind: String,
+    pub title: String,
+    pub issuer: String,
+    pub issued_on: Option<NaiveDate>,
+    /// Scans or copies of the diploma or certificate.
+    pub attachment_urls: Vec<String>,
+    pub status: String,
+    pub review_reason: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CredentialDto {
+    pub kind: String,
+    pub title: String,
+    pub issuer: String,
+    pub issued_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub attachment_urls: Vec<String>,
+}
+
+impl From<web::Json<CredentialDto>> for CredentialDto {
+    fn from(value: web::Json<CredentialDto>) -> Self {
+        CredentialDto {
+            kind: value.kind.clone(),
+            title: value.title.clone(),
+            issuer: value.issuer.clone(),
+            issued_on: value.issued_on,
+            attachment_urls: value.attachment_urls.clone(),
+        }
+    }
+}
+
+impl CredentialDto {
+    pub fn validate(&self) -> Result<(), EzyTutorError> {
+        if ![DEGREE, CERTIFICATE].contains(&self.kind.as_str()) {
+            return Err(EzyTutorError::InvalidInput(format!("Credential kind must be {} or {}", DEGREE, CERTIFICATE)));
+        }
+        if self.title.trim().is_empty() || self.issuer.trim().is_empty() {
+            return Err(EzyTutorError::InvalidInput("Credential title and issuer are required".to_string()));
+        }
+        if self.attachment_urls.iter().any(|url| !url.starts_with("https://") && !url.starts_with("http://")) {
+            return Err(EzyTutorError::InvalidInput("Attachments must be http or https URLs".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of an admin review: `approved`, or `rejected` with a `reason`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReviewCredentialDto {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+impl From<web::Json<ReviewCredentialDto>> for ReviewCredentialDto {
+    fn from(value: web::Json<ReviewCredentialDto>) -> Self {
+        ReviewCredentialDto {
+            status: value.status.clone(),
+            reason: value.reason.clone(),
+        }
+    }
+}
+
+impl ReviewCredentialDto {
+    pub fn validate(&self) -> Result<(), EzyTutorError> {
+        match self.status.as_str() {
+            APPROVED => Ok(()),
+            REJECTED if self.reason.as_deref().is_some_and(|reason| !reason.trim().is_empty()) => Ok(()),
+            REJECTED => Err(EzyTutorError::InvalidInput("Rejecting a credential needs a reason".to_string())),
+            _ => Err(EzyTutorError::InvalidInput(format!("Review status must be {} or {}", APPROVED, REJECTED))),
+        }
+    }
+}
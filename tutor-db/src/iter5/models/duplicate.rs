@@ -0,0 +1,92 @@
+#[cfg(any(test, feature = "sqlite"))]
+use std::collections::HashSet;
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::models::tutor::Tutor;
+
+/// Trigram similarity (pg_trgm) from which two tutor names are taken for the same person.
+pub const DUPLICATE_NAME_SIMILARITY: f32 = 0.6;
+/// Lower name similarity that is enough when both tutors have the same picture. A shared
+/// picture alone means nothing, many tutors keep the same placeholder avatar. It matches the
+/// default `pg_trgm.similarity_threshold` of the `%` operator.
+pub const DUPLICATE_PIC_NAME_SIMILARITY: f32 = 0.3;
+
+/// The pg_trgm similarity of two names, for the stores that do not have the extension: the
+/// share of distinct trigrams they have in common, each lowercased word padded with two spaces
+/// before and one after.
+#[cfg(any(test, feature = "sqlite"))]
+pub fn name_similarity(a: &str, b: &str) -> f32 {
+    let a = trigrams(a);
+    let b = trigrams(b);
+    let common = a.intersection(&b).count();
+    let all = a.union(&b).count();
+    if all == 0 {
+        return 0.0;
+    }
+    common as f32 / all as f32
+}
+
+#[cfg(any(test, feature = "sqlite"))]
+fn trigrams(name: &str) -> HashSet<String> {
+    let mut trigrams = HashSet::new();
+    for word in name.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+        let padded: Vec<char> = format!("  {} ", word).chars().collect();
+        trigrams.extend(padded.windows(3).map(|window| window.iter().collect::<String>()));
+    }
+    trigrams
+}
+
+/// Query string of `POST /tutors/`: a tutor that looks like an existing one is refused unless
+/// `allow_duplicate` is set.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CreateTutorQuery {
+    pub allow_duplicate: Option<bool>,
+}
+
+/// Two active tutors that may be the same person. `tutor` is the older of the two, so it is
+/// the one to keep when merging.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PossibleDuplicate {
+    pub tutor_id: Uuid,
+    pub tutor_name: String,
+    pub duplicate_id: Uuid,
+    pub duplicate_name: String,
+    pub name_similarity: f32,
+    pub same_pic_url: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MergeTutorDto {
+    pub duplicate_id: Uuid,
+}
+
+impl From<web::Json<MergeTutorDto>> for MergeTutorDto {
+    fn from(value: web::Json<MergeTutorDto>) -> Self {
+        MergeTutorDto {
+            duplicate_id: value.duplicate_id,
+        }
+    }
+}
+
+/// Outcome of merging `duplicate` into `tutor`: the courses it was a member of, its coupons,
+/// credentials and ratings now belong to `tutor`, and `duplicate` is soft deleted.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TutorMerge {
+    pub tutor: Tutor,
+    pub duplicate: Tutor,
+    pub moved_courses: Vec<Uuid>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn name_similarity_matches_pg_trgm() {
+        assert_eq!(name_similarity("word", "two words"), 4.0 / 11.0);
+        assert_eq!(name_similarity("Jane Doe", "jane  doe!"), 1.0);
+        assert_eq!(name_similarity("O'Brien", "obrien"), 0.5);
+        assert_eq!(name_similarity("", "anything"), 0.0);
+    }
+}
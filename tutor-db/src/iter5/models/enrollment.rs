@@ -0,0 +1,65 @@
+use actix_web::web;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How long a seat freed by a withdrawal is held for the waitlisted student it was offered to.
+pub const OFFER_HOLD_HOURS: i64 = 24;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct CourseCapacity {
+    pub course_id: Uuid,
+    pub capacity: i32,
+    pub enrolled: i64,
+    pub held: i64,
+    pub available: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct Enrollment {
+    pub course_id: Uuid,
+    pub student_id: Uuid,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct WaitlistEntry {
+    pub id: Uuid,
+    pub course_id: Uuid,
+    pub student_id: Uuid,
+    pub status: String,
+    /// 1-based place in the queue, only set while the entry is still waiting.
+    pub position: Option<i64>,
+    pub offered_at: Option<NaiveDateTime>,
+    pub hold_expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SetCapacityDto {
+    pub capacity: i32,
+}
+
+impl From<web::Json<SetCapacityDto>> for SetCapacityDto {
+    fn from(value: web::Json<SetCapacityDto>) -> Self {
+        SetCapacityDto {
+            capacity: value.capacity,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StudentDto {
+    pub student_id: Uuid,
+}
+
+impl From<web::Json<StudentDto>> for StudentDto {
+    fn from(value: web::Json<StudentDto>) -> Self {
+        StudentDto {
+            student_id: value.student_id,
+        }
+    }
+}
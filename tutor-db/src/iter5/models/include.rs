@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use crate::errors::EzyTutorError;
+
+pub const COURSES: &str = "courses";
+pub const TUTOR: &str = "tutor";
+
+/// The `include` query parameter: a comma separated list of related resources to embed.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct IncludeQuery {
+    pub include: Option<String>,
+}
+
+impl IncludeQuery {
+    /// Whether `relation` was asked for. Fails when anything outside `allowed` was asked for.
+    /// Empty items, as in `?include=` or `?include=courses,`, are ignored.
+    pub fn wants(&self, relation: &str, allowed: &[&str]) -> Result<bool, EzyTutorError> {
+        let mut wanted = false;
+        let requested_items = self.include.iter()
+            .flat_map(|include| include.split(','))
+            .map(str::trim)
+            .filter(|requested| !requested.is_empty());
+        for requested in requested_items {
+            if !allowed.contains(&requested) {
+                return Err(EzyTutorError::InvalidInput(format!("Can not include {}, only {}", requested, allowed.join(", "))));
+            }
+            wanted |= requested == relation;
+        }
+        Ok(wanted)
+    }
+
+    /// The relations of `allowed` that were asked for, in the order of `allowed`.
+    pub fn included<'a>(&self, allowed: &[&'a str]) -> Result<Vec<&'a str>, EzyTutorError> {
+        let mut included = vec![];
+        for relation in allowed {
+            if self.wants(relation, allowed)? {
+                included.push(*relation);
+            }
+        }
+        Ok(included)
+    }
+}
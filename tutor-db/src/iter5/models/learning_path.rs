@@ -0,0 +1,52 @@
+use actix_web::web;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::models::course::Course;
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct LearningPath {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub price: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct LearningPathCourse {
+    pub path_id: Uuid,
+    pub course_id: Uuid,
+    pub position: i32,
+}
+
+/// A path with its active courses in path order. Soft-deleted courses are left out.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct LearningPathDetail {
+    #[serde(flatten)]
+    pub path: LearningPath,
+    pub courses: Vec<Course>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LearningPathDto {
+    pub title: String,
+    pub description: Option<String>,
+    pub price: Option<i32>,
+    /// Member courses, in the order they should be taken.
+    pub course_ids: Vec<Uuid>,
+}
+
+impl From<web::Json<LearningPathDto>> for LearningPathDto {
+    fn from(value: web::Json<LearningPathDto>) -> Self {
+        LearningPathDto {
+            title: value.title.clone(),
+            description: value.description.clone(),
+            price: value.price,
+            course_ids: value.course_ids.clone(),
+        }
+    }
+}
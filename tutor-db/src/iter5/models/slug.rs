@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlugOwner {
+    Tutor(Uuid),
+    Course(Uuid),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct SlugRecord {
+    pub slug: String,
+    pub tutor_id: Option<Uuid>,
+    pub course_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+impl SlugRecord {
+    pub fn owner(&self) -> Option<SlugOwner> {
+        match (self.tutor_id, self.course_id) {
+            (Some(tutor_id), _) => Some(SlugOwner::Tutor(tutor_id)),
+            (_, Some(course_id)) => Some(SlugOwner::Course(course_id)),
+            _ => None,
+        }
+    }
+}
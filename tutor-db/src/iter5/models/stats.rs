@@ -0,0 +1,53 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_BUCKETS: i32 = 12;
+pub const MAX_BUCKETS: i32 = 104;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsBucket {
+    Week,
+    Month,
+}
+
+impl StatsBucket {
+    /// The unit as understood by Postgres' `date_trunc`.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            StatsBucket::Week => "week",
+            StatsBucket::Month => "month",
+        }
+    }
+}
+
+/// Query string of `GET /tutors/{id}/stats`: activity is reported for the last `buckets`
+/// weeks or months, the current one included.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct StatsQuery {
+    pub bucket: Option<StatsBucket>,
+    pub buckets: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ActivityBucket {
+    pub starts_on: NaiveDate,
+    pub courses_created: i64,
+    pub enrollments: i64,
+}
+
+/// Figures over every course the tutor teaches. Prices only consider active courses.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TutorStats {
+    pub tutor_id: Uuid,
+    pub active_courses: i64,
+    pub deleted_courses: i64,
+    pub average_price: Option<f64>,
+    pub min_price: Option<i32>,
+    pub max_price: Option<i32>,
+    pub total_enrollments: i64,
+    pub average_rating: Option<f64>,
+    pub ratings: i64,
+    pub activity: Vec<ActivityBucket>,
+}
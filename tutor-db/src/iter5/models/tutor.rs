@@ -1,8 +1,12 @@
 use actix_web::web;
 use actix_web::web::Json;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::course::Course;
 
 #[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialOrd, PartialEq)]
 pub struct Tutor {
@@ -14,6 +18,22 @@ pub struct Tutor {
     pub updated_at: Option<NaiveDateTime>,
     #[serde(skip_serializing)]
     pub deleted_at: Option<NaiveDateTime>,
+    pub slug: String,
+    /// `profile` rendered from Markdown and sanitized.
+    pub profile_html: String,
+    /// Set once an admin approved at least one of the tutor's credentials.
+    pub verified: bool,
+    /// Bumped by every update, the `ETag` of the tutor.
+    pub version: i32,
+}
+
+/// A tutor with the active courses it teaches, when they were asked for with `?include=courses`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TutorDetail {
+    #[serde(flatten)]
+    pub tutor: Tutor,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub courses: Option<Vec<Course>>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -33,11 +53,34 @@ impl From<web::Json<CreateTutorDto>> for CreateTutorDto {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// An `application/merge-patch+json` document (RFC 7396). The outer `Option` tells whether a
+/// member is present at all, the inner one whether it is `null`.
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct PatchTutorDto {
-    pub name: Option<String>,
-    pub pic_url: Option<String>,
-    pub profile: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub pic_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub profile: Option<Option<String>>,
+}
+
+/// Wraps any member that is present in the patch, `null` included, in `Some`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where D: Deserializer<'de>, T: Deserialize<'de> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl PatchTutorDto {
+    /// Every tutor field is required, so removing one with `null` is rejected.
+    pub fn validate(&self) -> Result<(), EzyTutorError> {
+        for (field, value) in [("name", &self.name), ("pic_url", &self.pic_url), ("profile", &self.profile)] {
+            if let Some(None) = value {
+                return Err(EzyTutorError::InvalidInput(format!("Tutor {} can not be removed", field)));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl From<web::Json<PatchTutorDto>> for PatchTutorDto {
@@ -48,4 +91,112 @@ impl From<web::Json<PatchTutorDto>> for PatchTutorDto {
             profile: value.profile.clone(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Default and maximum page sizes of the tutor listing.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TutorSort {
+    /// Oldest first, the default.
+    Created,
+    /// Alphabetical, case insensitive.
+    Name,
+    /// Best average rating first, unrated tutors last.
+    Rating,
+}
+
+/// Where a page of `GET /tutors/` stops: the sort key and id of its last tutor as they were
+/// when the page was read, so that renames and new ratings do not move the next page.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "sort", rename_all = "lowercase")]
+pub enum TutorCursor {
+    Created { created_at: NaiveDateTime, id: Uuid },
+    /// Compared case insensitively, as the listing is sorted.
+    Name { name: String, id: Uuid },
+    Rating { rating: f64, id: Uuid },
+}
+
+impl TutorCursor {
+    pub fn sort(&self) -> TutorSort {
+        match self {
+            TutorCursor::Created { .. } => TutorSort::Created,
+            TutorCursor::Name { .. } => TutorSort::Name,
+            TutorCursor::Rating { .. } => TutorSort::Rating,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            TutorCursor::Created { id, .. } | TutorCursor::Name { id, .. } | TutorCursor::Rating { id, .. } => *id,
+        }
+    }
+
+    /// Position of `tutor` in a listing by `sort`, `rating` being the average it was sorted by.
+    pub fn of(sort: TutorSort, tutor: &Tutor, rating: f64) -> Self {
+        match sort {
+            TutorSort::Created => TutorCursor::Created { created_at: tutor.created_at, id: tutor.id },
+            TutorSort::Name => TutorCursor::Name { name: tutor.name.clone(), id: tutor.id },
+            TutorSort::Rating => TutorCursor::Rating { rating, id: tutor.id },
+        }
+    }
+
+    /// The opaque form sent as `after`.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, EzyTutorError> {
+        URL_SAFE_NO_PAD.decode(cursor).ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or(EzyTutorError::InvalidInput("Invalid page cursor".to_string()))
+    }
+}
+
+/// Query string of `GET /tutors/`. `after` is the cursor of the previous page, as found in its
+/// `next` link.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TutorListQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<TutorSort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TutorPage {
+    pub tutors: Vec<Tutor>,
+    pub next: Option<TutorCursor>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, sqlx::FromRow, PartialEq)]
+pub struct TutorRating {
+    pub tutor_id: Uuid,
+    pub student_id: Uuid,
+    pub rating: i16,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RateTutorDto {
+    pub rating: i16,
+}
+
+impl From<web::Json<RateTutorDto>> for RateTutorDto {
+    fn from(value: Json<RateTutorDto>) -> Self {
+        RateTutorDto {
+            rating: value.rating,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use actix_web::guard::GuardContext;
+use actix_web::http::header;
+use json_patch::Patch;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use crate::errors::EzyTutorError;
+
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+/// Route guard for `PATCH` requests carrying an RFC 6902 operation list.
+pub fn is_json_patch(ctx: &GuardContext) -> bool {
+    ctx.header::<header::ContentType>()
+        .is_some_and(|content_type| content_type.0.essence_str() == JSON_PATCH)
+}
+
+/// Applies `patch` to the JSON representation of `current` and reads the result back as `D`.
+/// Nothing is applied unless every operation succeeds, members can not be added, and the
+/// `read_only` members must come out unchanged.
+pub fn apply<T: Serialize, D: DeserializeOwned>(current: &T, patch: &Patch, read_only: &[&str]) -> Result<D, EzyTutorError> {
+    let original = serde_json::to_value(current).map_err(|err| EzyTutorError::ActixError(err.to_string()))?;
+    let mut document = original.clone();
+    json_patch::patch(&mut document, patch)?;
+
+    let (Value::Object(before), Value::Object(after)) = (&original, &document) else {
+        return Err(EzyTutorError::InvalidInput("The patched document must remain an object".to_string()));
+    };
+    if let Some(member) = after.keys().find(|member| !before.contains_key(*member)) {
+        return Err(EzyTutorError::InvalidInput(format!("Unknown member {}", member)));
+    }
+    if let Some(member) = read_only.iter().find(|member| before.get(**member) != after.get(**member)) {
+        return Err(EzyTutorError::InvalidInput(format!("Member {} is read only", member)));
+    }
+
+    serde_json::from_value(document).map_err(|err| EzyTutorError::InvalidInput(err.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+    use serde_json::json;
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Document {
+        id: u32,
+        name: String,
+        level: Option<String>,
+    }
+
+    fn patch(ops: Value) -> Patch {
+        serde_json::from_value(ops).unwrap()
+    }
+
+    #[test]
+    fn applies_every_operation_or_none() {
+        let current = Document { id: 1, name: "Optics".into(), level: Some("Beginner".into()) };
+
+        let patched: Document = apply(&current, &patch(json!([
+            { "op": "test", "path": "/name", "value": "Optics" },
+            { "op": "replace", "path": "/name", "value": "Basic optics" },
+            { "op": "remove", "path": "/level" },
+        ])), &["id"]).unwrap();
+        assert_eq!(patched, Document { id: 1, name: "Basic optics".into(), level: None });
+
+        let failed = apply::<_, Document>(&current, &patch(json!([
+            { "op": "replace", "path": "/name", "value": "Basic optics" },
+            { "op": "test", "path": "/name", "value": "Optics" },
+        ])), &["id"]);
+        assert!(matches!(failed, Err(EzyTutorError::Conflict(_))));
+
+        let failed = apply::<_, Document>(&current, &patch(json!([{ "op": "replace", "path": "/id", "value": 2 }])), &["id"]);
+        assert!(matches!(failed, Err(EzyTutorError::InvalidInput(_))));
+
+        let failed = apply::<_, Document>(&current, &patch(json!([{ "op": "add", "path": "/rating", "value": 5 }])), &["id"]);
+        assert!(matches!(failed, Err(EzyTutorError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,92 @@
+use actix_web::http::header::{ETag, EntityTag, IfMatch};
+use actix_web::web;
+use crate::errors::EzyTutorError;
+
+/// The `ETag` of a tutor or course, its version as a strong entity tag.
+pub fn etag(version: i32) -> ETag {
+    ETag(EntityTag::new_strong(version.to_string()))
+}
+
+/// The `ETag` of a tutor or course as read with `locales` and the `included` relations embedded.
+/// Those change the body, so they follow the version in the tag, as in `"3;fr,en;tutor"`, and
+/// only the representation without either is tagged with the bare version.
+pub fn representation_etag(version: i32, locales: &[String], included: &[&str]) -> ETag {
+    if locales.is_empty() && included.is_empty() {
+        return etag(version);
+    }
+    ETag(EntityTag::new_strong(format!("{};{};{}", version, locales.join(","), included.join(","))))
+}
+
+/// The versions of a tutor or course a write may apply to, taken from `If-Match`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpectedVersion {
+    /// `If-Match: *`, or no `If-Match` where it is not required.
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl ExpectedVersion {
+    /// Reads `If-Match`, refusing a write without one when it is `required`. Only the version a tag
+    /// starts with is compared, so the tag of any representation that was read can be sent back.
+    /// Weak tags and tags that are not a version never match, as in the strong comparison
+    /// `If-Match` asks for.
+    pub fn from_header(if_match: Option<web::Header<IfMatch>>, required: bool) -> Result<Self, EzyTutorError> {
+        // actix parses a missing `If-Match` as an empty list
+        let if_match = if_match.map(web::Header::into_inner)
+            .filter(|if_match| !matches!(if_match, IfMatch::Items(tags) if tags.is_empty()));
+        match if_match {
+            None if required => Err(EzyTutorError::PreconditionRequired("If-Match is required to change this resource".to_string())),
+            None | Some(IfMatch::Any) => Ok(ExpectedVersion::Any),
+            Some(IfMatch::Items(tags)) => Ok(ExpectedVersion::Versions(tags.iter()
+                .filter(|tag| !tag.weak)
+                .filter_map(|tag| tag.tag().split(';').next()?.parse().ok())
+                .collect())),
+        }
+    }
+
+    /// Fails with `PreconditionFailed` when the resource is no longer at an expected version.
+    pub fn check(&self, version: i32) -> Result<(), EzyTutorError> {
+        match self {
+            ExpectedVersion::Versions(versions) if !versions.contains(&version) => {
+                Err(EzyTutorError::PreconditionFailed(format!("Resource has changed, its version is now {}", version)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn if_match(tags: Vec<EntityTag>) -> Option<web::Header<IfMatch>> {
+        Some(web::Header(IfMatch::Items(tags)))
+    }
+
+    #[test]
+    fn if_match_compares_strong_versions() {
+        let expected = ExpectedVersion::from_header(if_match(vec![EntityTag::new_strong("3".into())]), true).unwrap();
+        assert!(expected.check(3).is_ok());
+        assert!(matches!(expected.check(4), Err(EzyTutorError::PreconditionFailed(_))));
+
+        let weak = ExpectedVersion::from_header(if_match(vec![EntityTag::new_weak("3".into())]), true).unwrap();
+        assert!(weak.check(3).is_err());
+        let any = ExpectedVersion::from_header(Some(web::Header(IfMatch::Any)), true).unwrap();
+        assert!(any.check(3).is_ok());
+
+        assert!(ExpectedVersion::from_header(None, false).unwrap().check(3).is_ok());
+        assert!(ExpectedVersion::from_header(if_match(vec![]), false).unwrap().check(3).is_ok());
+        assert!(matches!(ExpectedVersion::from_header(None, true), Err(EzyTutorError::PreconditionRequired(_))));
+    }
+
+    #[test]
+    fn representations_get_their_own_tag() {
+        let locales = vec!["fr".to_string(), "en".to_string()];
+        assert_eq!(representation_etag(3, &[], &[]), etag(3));
+        assert_eq!(representation_etag(3, &locales, &[]).to_string(), "\"3;fr,en;\"");
+        assert_eq!(representation_etag(3, &[], &["tutor"]).to_string(), "\"3;;tutor\"");
+
+        let ETag(tag) = representation_etag(3, &locales, &["tutor"]);
+        assert!(ExpectedVersion::from_header(if_match(vec![tag]), true).unwrap().check(3).is_ok());
+    }
+}
@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::pin::Pin;
+use actix_web::{web, FromRequest, HttpRequest};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{Payload, ServiceResponse};
+use log::warn;
+use sqlx::PgPool;
+use crate::errors::EzyTutorError;
+use crate::state::AppState;
+
+/// Holds the WAL position of the primary after the last write of a client, which the replica has
+/// to have replayed before it serves the reads of that client.
+pub const WRITTEN_LSN_COOKIE: &str = "ezy_written_lsn";
+
+/// Where a read may be served from, taken from `WRITTEN_LSN_COOKIE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Consistency {
+    /// The replica, which has every write of the client or is not used.
+    Eventual,
+    /// The primary, so that a client sees the writes the replica has not replayed yet.
+    ReadYourWrites,
+}
+
+impl FromRequest for Consistency {
+    type Error = EzyTutorError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let written = req.cookie(WRITTEN_LSN_COOKIE).map(|cookie| cookie.value().to_string());
+        let replica = req.app_data::<web::Data<AppState>>().and_then(|app_state| app_state.replica_db.clone());
+        Box::pin(async move {
+            let (Some(written), Some(replica)) = (written, replica) else {
+                return Ok(Consistency::Eventual);
+            };
+            match has_replayed(&replica, &written).await {
+                Ok(true) => Ok(Consistency::Eventual),
+                Ok(false) => Ok(Consistency::ReadYourWrites),
+                Err(err) => {
+                    warn!("Could not compare the replica with {}, reading from the primary: {:?}", written, err);
+                    Ok(Consistency::ReadYourWrites)
+                }
+            }
+        })
+    }
+}
+
+/// Whether `replica` has replayed the primary's WAL up to `lsn`. A replica URL that points to a
+/// primary, as in development, has every write.
+async fn has_replayed(replica: &PgPool, lsn: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT coalesce(pg_last_wal_replay_lsn(), pg_current_wal_lsn()) >= $1::text::pg_lsn")
+        .bind(lsn)
+        .fetch_one(replica)
+        .await
+}
+
+/// Pins the reads of the client to the primary after a successful write until the replica has
+/// caught up with it, by keeping the WAL position of the primary in `WRITTEN_LSN_COOKIE`. Nothing
+/// is kept without a replica, where every read goes to the primary anyway.
+pub async fn pin_after_write<B>(res: &mut ServiceResponse<B>) {
+    if res.request().method().is_safe() || !res.status().is_success() {
+        return;
+    }
+    let Some(app_state) = res.request().app_data::<web::Data<AppState>>().cloned() else {
+        return;
+    };
+    if app_state.replica_db.is_none() {
+        return;
+    }
+    let lsn: String = match sqlx::query_scalar("SELECT pg_current_wal_lsn()::text").fetch_one(&app_state.db).await {
+        Ok(lsn) => lsn,
+        Err(err) => {
+            warn!("Could not read the WAL position of the primary, the next reads may not see this write: {:?}", err);
+            return;
+        }
+    };
+    let cookie = Cookie::build(WRITTEN_LSN_COOKIE, lsn)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .finish();
+    // Only fails for a header value that can not be encoded, which an LSN never is
+    let _ = res.response_mut().add_cookie(&cookie);
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use actix_web::http::Method;
+    use actix_web::HttpResponse;
+    use actix_web::test::TestRequest;
+    use dotenv::dotenv;
+    use super::*;
+
+    async fn consistency_of(req: TestRequest) -> Consistency {
+        Consistency::extract(&req.to_http_request()).await.unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn writes_pin_the_client_to_the_primary_until_the_replica_replays_them() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        // The primary serves as its own replica, which is always caught up
+        let app_state = web::Data::new(AppState::new("".to_string(), pool.clone()).with_replica_pool(pool.clone()));
+        let request = || TestRequest::default().app_data(app_state.clone());
+
+        let mut write = request().method(Method::POST).to_srv_response(HttpResponse::Created().finish());
+        pin_after_write(&mut write).await;
+        let cookie = write.response().cookies().find(|cookie| cookie.name() == WRITTEN_LSN_COOKIE).unwrap().into_owned();
+        assert_eq!(consistency_of(request().cookie(cookie)).await, Consistency::Eventual);
+        assert_eq!(consistency_of(request()).await, Consistency::Eventual);
+
+        let ahead = Cookie::new(WRITTEN_LSN_COOKIE, "FFFFFFFF/FFFFFFFF");
+        assert_eq!(consistency_of(request().cookie(ahead)).await, Consistency::ReadYourWrites);
+        let invalid = Cookie::new(WRITTEN_LSN_COOKIE, "not-an-lsn");
+        assert_eq!(consistency_of(request().cookie(invalid)).await, Consistency::ReadYourWrites);
+
+        let mut read = request().to_srv_response(HttpResponse::Ok().finish());
+        pin_after_write(&mut read).await;
+        let mut failed = request().method(Method::PUT).to_srv_response(HttpResponse::PreconditionFailed().finish());
+        pin_after_write(&mut failed).await;
+        let without_replica = web::Data::new(AppState::new("".to_string(), pool));
+        let mut unreplicated = TestRequest::default().app_data(without_replica).method(Method::POST).to_srv_response(HttpResponse::Created().finish());
+        pin_after_write(&mut unreplicated).await;
+        let cookies = [read, failed, unreplicated].iter().map(|res| res.response().cookies().count()).sum::<usize>();
+        assert_eq!(cookies, 0);
+    }
+}
@@ -0,0 +1,428 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use uuid::Uuid;
+use crate::dbaccess::slug::{base_slug, candidates};
+use crate::errors::EzyTutorError;
+use crate::markdown;
+use crate::models::course::{Course, CourseTranslation, CreateCourseDto, UpsertCourseTranslationDto};
+use crate::models::course_tutor::{CO_TUTOR, CourseTutor, OWNER};
+use crate::models::slug::SlugOwner;
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor, TutorCursor, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+use crate::repository::{CourseChanges, CourseRepository, likely_duplicates, TutorChanges, TutorRepository};
+
+#[derive(Default)]
+struct Store {
+    tutors: HashMap<Uuid, Tutor>,
+    courses: HashMap<Uuid, Course>,
+    memberships: Vec<CourseTutor>,
+    slugs: HashMap<String, SlugOwner>,
+    ratings: Vec<TutorRating>,
+    translations: Vec<CourseTranslation>,
+}
+
+/// The repositories kept in memory, with the same semantics as the Postgres ones, so handlers
+/// and services can be tested without a database. Every call holds the lock for its whole
+/// duration, which stands in for the row locks and transactions of Postgres.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    store: Mutex<Store>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn not_found() -> EzyTutorError {
+    EzyTutorError::NotFound("Requested resource not found".to_string())
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+/// Listing order of two positions of the same sort, the order `dbaccess::tutor::get_tutors`
+/// sorts by. Positions of different sorts never compare greater.
+fn compare_cursors(a: &TutorCursor, b: &TutorCursor) -> Ordering {
+    match (a, b) {
+        (TutorCursor::Created { created_at: a_at, id: a_id }, TutorCursor::Created { created_at: b_at, id: b_id }) => {
+            (a_at, a_id).cmp(&(b_at, b_id))
+        }
+        (TutorCursor::Name { name: a_name, id: a_id }, TutorCursor::Name { name: b_name, id: b_id }) => {
+            (a_name.to_lowercase(), a_id).cmp(&(b_name.to_lowercase(), b_id))
+        }
+        (TutorCursor::Rating { rating: a_rating, id: a_id }, TutorCursor::Rating { rating: b_rating, id: b_id }) => {
+            b_rating.total_cmp(a_rating).then(a_id.cmp(b_id))
+        }
+        _ => Ordering::Equal,
+    }
+}
+
+impl Store {
+    /// Same rules as `dbaccess::slug::available_slug`, and records the slug right away.
+    fn claim_slug(&mut self, owner: SlugOwner, name: &str) -> String {
+        let slug = candidates(base_slug(owner, name))
+            .find(|candidate| match self.slugs.get(candidate) {
+                None => true,
+                Some(taken_by) => *taken_by == owner,
+            })
+            .expect("the candidate slugs are unbounded");
+        self.slugs.insert(slug.clone(), owner);
+        slug
+    }
+
+    fn role_of(&self, course_id: Uuid, tutor_id: Uuid) -> Option<String> {
+        self.courses.get(&course_id).filter(|course| course.deleted_at.is_none())?;
+        self.memberships.iter()
+            .find(|membership| membership.course_id == course_id && membership.tutor_id == tutor_id)
+            .map(|membership| membership.role.clone())
+    }
+
+    fn active_tutor(&mut self, id: Uuid) -> Result<&mut Tutor, EzyTutorError> {
+        self.tutors.get_mut(&id)
+            .filter(|tutor| tutor.deleted_at.is_none())
+            .ok_or_else(not_found)
+    }
+
+    fn average_rating(&self, tutor_id: Uuid) -> f64 {
+        let ratings: Vec<f64> = self.ratings.iter()
+            .filter(|rating| rating.tutor_id == tutor_id)
+            .map(|rating| rating.rating as f64)
+            .collect();
+        if ratings.is_empty() {
+            0.0
+        } else {
+            ratings.iter().sum::<f64>() / ratings.len() as f64
+        }
+    }
+}
+
+#[async_trait]
+impl CourseRepository for InMemoryRepository {
+    async fn get_courses_by_tutor(&self, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError> {
+        let store = self.store();
+        let mut courses: Vec<Course> = store.memberships.iter()
+            .filter(|membership| membership.tutor_id == tutor_id)
+            .filter_map(|membership| store.courses.get(&membership.course_id))
+            .filter(|course| course.deleted_at.is_none())
+            .cloned()
+            .collect();
+        courses.sort_by_key(|course| (course.created_at, course.id));
+        Ok(courses)
+    }
+
+    async fn get_courses_by_ids(&self, course_ids: &[Uuid]) -> Result<Vec<Course>, EzyTutorError> {
+        let store = self.store();
+        Ok(store.courses.values()
+            .filter(|course| course_ids.contains(&course.id) && course.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_course(&self, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
+        let store = self.store();
+        store.role_of(course_id, tutor_id).ok_or_else(not_found)?;
+        Ok(store.courses[&course_id].clone())
+    }
+
+    async fn new_course(&self, course: CreateCourseDto) -> Result<Course, EzyTutorError> {
+        let mut store = self.store();
+        if !store.tutors.contains_key(&course.tutor_id) {
+            return Err(EzyTutorError::DBError(format!("Tutor {} does not exist", course.tutor_id)));
+        }
+        let id = Uuid::new_v4();
+        let register_time = now();
+        let slug = store.claim_slug(SlugOwner::Course(id), &course.name);
+        let inserted_course = Course {
+            id,
+            tutor_id: course.tutor_id,
+            description_html: course.description.as_deref().map(markdown::to_html),
+            name: course.name,
+            description: course.description,
+            format: course.format,
+            structure: course.structure,
+            duration: course.duration,
+            price: course.price,
+            language: course.language,
+            level: course.level,
+            posted_time: register_time,
+            created_at: register_time,
+            updated_at: Some(register_time),
+            deleted_at: None,
+            slug,
+            version: 1,
+        };
+        store.courses.insert(id, inserted_course.clone());
+        store.memberships.push(CourseTutor {
+            course_id: id,
+            tutor_id: inserted_course.tutor_id,
+            role: OWNER.to_string(),
+            created_at: register_time,
+            updated_at: Some(register_time),
+        });
+        Ok(inserted_course)
+    }
+
+    async fn update_course_with(&self, tutor_id: Uuid, course_id: Uuid, changes: CourseChanges<'_>) -> Result<Course, EzyTutorError> {
+        let mut store = self.store();
+        match store.role_of(course_id, tutor_id) {
+            Some(role) if role == OWNER || role == CO_TUTOR => {}
+            _ => return Err(not_found()),
+        }
+        let update_data = changes(&store.courses[&course_id])?;
+        let slug = if store.courses[&course_id].name == update_data.name {
+            store.courses[&course_id].slug.clone()
+        } else {
+            store.claim_slug(SlugOwner::Course(course_id), &update_data.name)
+        };
+
+        let course = store.courses.get_mut(&course_id).ok_or_else(not_found)?;
+        course.description_html = update_data.description.as_deref().map(markdown::to_html);
+        course.name = update_data.name;
+        course.description = update_data.description;
+        course.format = update_data.format;
+        course.structure = update_data.structure;
+        course.duration = update_data.duration;
+        course.price = update_data.price;
+        course.language = update_data.language;
+        course.level = update_data.level;
+        course.slug = slug;
+        course.updated_at = Some(now());
+        course.version += 1;
+        Ok(course.clone())
+    }
+
+    async fn soft_delete_course(&self, tutor_id: Uuid, course_id: Uuid, expected: &ExpectedVersion) -> Result<Course, EzyTutorError> {
+        let mut store = self.store();
+        let course = store.courses.get_mut(&course_id)
+            .filter(|course| course.tutor_id == tutor_id && course.deleted_at.is_none())
+            .ok_or_else(not_found)?;
+        expected.check(course.version)?;
+        course.deleted_at = Some(now());
+        course.updated_at = course.deleted_at;
+        course.version += 1;
+        Ok(course.clone())
+    }
+
+    async fn get_memberships(&self, tutor_ids: &[Uuid]) -> Result<Vec<CourseTutor>, EzyTutorError> {
+        let store = self.store();
+        let mut memberships: Vec<CourseTutor> = store.memberships.iter()
+            .filter(|membership| tutor_ids.contains(&membership.tutor_id))
+            .cloned()
+            .collect();
+        memberships.sort_by_key(|membership| {
+            let course = &store.courses[&membership.course_id];
+            (course.created_at, course.id)
+        });
+        Ok(memberships)
+    }
+
+    async fn role_of(&self, course_id: Uuid, tutor_id: Uuid) -> Result<Option<String>, EzyTutorError> {
+        Ok(self.store().role_of(course_id, tutor_id))
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        self.store().slugs.get(slug).copied().ok_or_else(not_found)
+    }
+
+    async fn get_translations(&self, course_id: Uuid) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        let mut translations: Vec<CourseTranslation> = self.store().translations.iter()
+            .filter(|translation| translation.course_id == course_id)
+            .cloned()
+            .collect();
+        translations.sort_by(|a, b| a.locale.cmp(&b.locale));
+        Ok(translations)
+    }
+
+    async fn get_translations_for_courses(&self, course_ids: &[Uuid], locales: &[String]) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        Ok(self.store().translations.iter()
+            .filter(|translation| course_ids.contains(&translation.course_id) && locales.contains(&translation.locale))
+            .cloned()
+            .collect())
+    }
+
+    async fn upsert_translation(&self, course_id: Uuid, locale: String, dto: UpsertCourseTranslationDto) -> Result<CourseTranslation, EzyTutorError> {
+        let mut store = self.store();
+        if !store.courses.contains_key(&course_id) {
+            return Err(EzyTutorError::DBError(format!("Course {} does not exist", course_id)));
+        }
+        let register_time = now();
+        let existing = store.translations.iter_mut()
+            .find(|translation| translation.course_id == course_id && translation.locale == locale);
+        let translation = match existing {
+            Some(translation) => {
+                translation.name = dto.name;
+                translation.description = dto.description;
+                translation.updated_at = Some(register_time);
+                translation.clone()
+            }
+            None => {
+                let translation = CourseTranslation {
+                    course_id,
+                    locale,
+                    name: dto.name,
+                    description: dto.description,
+                    created_at: register_time,
+                    updated_at: Some(register_time),
+                };
+                store.translations.push(translation.clone());
+                translation
+            }
+        };
+        Ok(translation)
+    }
+
+    async fn delete_translation(&self, course_id: Uuid, locale: String) -> Result<CourseTranslation, EzyTutorError> {
+        let mut store = self.store();
+        let position = store.translations.iter()
+            .position(|translation| translation.course_id == course_id && translation.locale == locale)
+            .ok_or_else(not_found)?;
+        Ok(store.translations.remove(position))
+    }
+}
+
+#[async_trait]
+impl TutorRepository for InMemoryRepository {
+    async fn get_tutors(&self, sort: TutorSort, name_prefix: Option<&str>, verified: Option<bool>, after: Option<&TutorCursor>, limit: i64) -> Result<Vec<(Tutor, TutorCursor)>, EzyTutorError> {
+        let store = self.store();
+        let name_prefix = name_prefix.map(str::to_lowercase);
+        let mut tutors: Vec<(&Tutor, TutorCursor)> = store.tutors.values()
+            .filter(|tutor| tutor.deleted_at.is_none())
+            .filter(|tutor| name_prefix.as_ref().is_none_or(|prefix| tutor.name.to_lowercase().starts_with(prefix)))
+            .filter(|tutor| verified.is_none_or(|verified| tutor.verified == verified))
+            .map(|tutor| (tutor, TutorCursor::of(sort, tutor, store.average_rating(tutor.id))))
+            .collect();
+
+        tutors.sort_by(|(_, a), (_, b)| compare_cursors(a, b));
+        if let Some(after) = after {
+            tutors.retain(|(_, cursor)| compare_cursors(cursor, after) == Ordering::Greater);
+        }
+        Ok(tutors.into_iter().take(limit as usize).map(|(tutor, cursor)| (tutor.clone(), cursor)).collect())
+    }
+
+    async fn by_id(&self, id: Uuid) -> Result<Tutor, EzyTutorError> {
+        Ok(self.store().active_tutor(id)?.clone())
+    }
+
+    async fn get_tutors_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Tutor>, EzyTutorError> {
+        let store = self.store();
+        Ok(store.tutors.values()
+            .filter(|tutor| ids.contains(&tutor.id) && tutor.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn find_duplicates(&self, name: &str, pic_url: &str) -> Result<Vec<Tutor>, EzyTutorError> {
+        let tutors = self.store().tutors.values().cloned().collect();
+        Ok(likely_duplicates(tutors, name, pic_url))
+    }
+
+    async fn create(&self, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError> {
+        let mut store = self.store();
+        let id = Uuid::new_v4();
+        let slug = store.claim_slug(SlugOwner::Tutor(id), &dto.name);
+        let tutor = Tutor {
+            id,
+            profile_html: markdown::to_html(&dto.profile),
+            name: dto.name,
+            pic_url: dto.pic_url,
+            profile: dto.profile,
+            created_at: now(),
+            updated_at: Some(now()),
+            deleted_at: None,
+            slug,
+            verified: false,
+            version: 1,
+        };
+        store.tutors.insert(id, tutor.clone());
+        Ok(tutor)
+    }
+
+    async fn update_with(&self, id: Uuid, changes: TutorChanges<'_>) -> Result<Tutor, EzyTutorError> {
+        let mut store = self.store();
+        let update_data = changes(store.active_tutor(id)?)?;
+        let slug = if store.tutors[&id].name == update_data.name {
+            store.tutors[&id].slug.clone()
+        } else {
+            store.claim_slug(SlugOwner::Tutor(id), &update_data.name)
+        };
+
+        let tutor = store.active_tutor(id)?;
+        tutor.profile_html = markdown::to_html(&update_data.profile);
+        tutor.name = update_data.name;
+        tutor.pic_url = update_data.pic_url;
+        tutor.profile = update_data.profile;
+        tutor.slug = slug;
+        tutor.updated_at = Some(now());
+        tutor.version += 1;
+        Ok(tutor.clone())
+    }
+
+    async fn patch(&self, patch: PatchTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        let mut store = self.store();
+        let current = store.active_tutor(id)?.clone();
+        expected.check(current.version)?;
+        let slug = match patch.name.clone().flatten() {
+            Some(name) if name != current.name => store.claim_slug(SlugOwner::Tutor(id), &name),
+            _ => current.slug,
+        };
+
+        let tutor = store.active_tutor(id)?;
+        if let Some(name) = patch.name.flatten() {
+            tutor.name = name;
+        }
+        if let Some(pic_url) = patch.pic_url.flatten() {
+            tutor.pic_url = pic_url;
+        }
+        if let Some(profile) = patch.profile.flatten() {
+            tutor.profile_html = markdown::to_html(&profile);
+            tutor.profile = profile;
+        }
+        tutor.slug = slug;
+        tutor.updated_at = Some(now());
+        tutor.version += 1;
+        Ok(tutor.clone())
+    }
+
+    async fn soft_delete(&self, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        let mut store = self.store();
+        let tutor = store.active_tutor(id)?;
+        expected.check(tutor.version)?;
+        tutor.deleted_at = Some(now());
+        tutor.updated_at = tutor.deleted_at;
+        tutor.version += 1;
+        Ok(tutor.clone())
+    }
+
+    async fn rate(&self, tutor_id: Uuid, student_id: Uuid, rating: i16) -> Result<TutorRating, EzyTutorError> {
+        let mut store = self.store();
+        if !store.tutors.contains_key(&tutor_id) {
+            return Err(EzyTutorError::DBError(format!("Tutor {} does not exist", tutor_id)));
+        }
+        match store.ratings.iter_mut().find(|existing| existing.tutor_id == tutor_id && existing.student_id == student_id) {
+            Some(existing) => {
+                existing.rating = rating;
+                existing.updated_at = Some(now());
+                Ok(existing.clone())
+            }
+            None => {
+                let created = TutorRating { tutor_id, student_id, rating, created_at: now(), updated_at: Some(now()) };
+                store.ratings.push(created.clone());
+                Ok(created)
+            }
+        }
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        self.store().slugs.get(slug).copied().ok_or_else(not_found)
+    }
+}
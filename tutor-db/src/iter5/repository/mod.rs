@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::errors::EzyTutorError;
+use crate::models::course::{Course, CourseTranslation, CreateCourseDto, UpdateCourseDto, UpsertCourseTranslationDto};
+use crate::models::course_tutor::CourseTutor;
+#[cfg(any(test, feature = "sqlite"))]
+use crate::models::duplicate::{DUPLICATE_NAME_SIMILARITY, DUPLICATE_PIC_NAME_SIMILARITY, name_similarity};
+use crate::models::slug::SlugOwner;
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor, TutorCursor, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Builds the new state of a course from its current one, see `CourseRepository::update_course_with`.
+pub type CourseChanges<'a> = Box<dyn FnOnce(&Course) -> Result<UpdateCourseDto, EzyTutorError> + Send + 'a>;
+/// Builds the new state of a tutor from its current one, see `TutorRepository::update_with`.
+pub type TutorChanges<'a> = Box<dyn FnOnce(&Tutor) -> Result<CreateTutorDto, EzyTutorError> + Send + 'a>;
+
+/// Keeps the `tutors` that `find_duplicates` flags, most similar name first, for the stores
+/// that compare names outside of the database.
+#[cfg(any(test, feature = "sqlite"))]
+fn likely_duplicates(tutors: Vec<Tutor>, name: &str, pic_url: &str) -> Vec<Tutor> {
+    let mut scored: Vec<(f32, Tutor)> = tutors.into_iter()
+        .filter(|tutor| tutor.deleted_at.is_none())
+        .map(|tutor| (name_similarity(&tutor.name, name), tutor))
+        .filter(|(similarity, tutor)| {
+            *similarity >= if tutor.pic_url == pic_url { DUPLICATE_PIC_NAME_SIMILARITY } else { DUPLICATE_NAME_SIMILARITY }
+        })
+        .collect();
+    scored.sort_by(|(a, a_tutor), (b, b_tutor)| b.total_cmp(a).then(a_tutor.id.cmp(&b_tutor.id)));
+    scored.into_iter().map(|(_, tutor)| tutor).collect()
+}
+
+/// Persistence of courses and of who teaches them. Soft deleted courses are never returned, and
+/// a course that does not exist for the tutor fails with `NotFound`. Every write bumps the
+/// version of the course.
+#[async_trait]
+pub trait CourseRepository: Send + Sync {
+    /// Active courses the tutor teaches, whatever its role.
+    async fn get_courses_by_tutor(&self, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError>;
+
+    async fn get_courses_by_ids(&self, course_ids: &[Uuid]) -> Result<Vec<Course>, EzyTutorError>;
+
+    async fn get_course(&self, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError>;
+
+    /// Creates the course with `tutor_id` as its owner.
+    async fn new_course(&self, course: CreateCourseDto) -> Result<Course, EzyTutorError>;
+
+    async fn update_course(&self, tutor_id: Uuid, course_id: Uuid, update_data: UpdateCourseDto, expected: &ExpectedVersion) -> Result<Course, EzyTutorError> {
+        self.update_course_with(tutor_id, course_id, Box::new(|current| {
+            expected.check(current.version)?;
+            Ok(update_data)
+        })).await
+    }
+
+    /// Updates a course the tutor owns or co-teaches from its current state, with nothing
+    /// written in between. The slug is regenerated when the name changes.
+    async fn update_course_with(&self, tutor_id: Uuid, course_id: Uuid, changes: CourseChanges<'_>) -> Result<Course, EzyTutorError>;
+
+    /// Soft deletes a course owned by the tutor, when it is still at the `expected` version.
+    async fn soft_delete_course(&self, tutor_id: Uuid, course_id: Uuid, expected: &ExpectedVersion) -> Result<Course, EzyTutorError>;
+
+    /// Courses taught by any of the tutors, whatever their role.
+    async fn get_memberships(&self, tutor_ids: &[Uuid]) -> Result<Vec<CourseTutor>, EzyTutorError>;
+
+    /// Role of the tutor on an active course, `None` when it does not teach it.
+    async fn role_of(&self, course_id: Uuid, tutor_id: Uuid) -> Result<Option<String>, EzyTutorError>;
+
+    /// Owner of a current or former slug of a tutor or course.
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError>;
+
+    /// Translations of the course, by locale.
+    async fn get_translations(&self, course_id: Uuid) -> Result<Vec<CourseTranslation>, EzyTutorError>;
+
+    /// Translations of any of the courses to any of the lowercase `locales`.
+    async fn get_translations_for_courses(&self, course_ids: &[Uuid], locales: &[String]) -> Result<Vec<CourseTranslation>, EzyTutorError>;
+
+    async fn upsert_translation(&self, course_id: Uuid, locale: String, dto: UpsertCourseTranslationDto) -> Result<CourseTranslation, EzyTutorError>;
+
+    async fn delete_translation(&self, course_id: Uuid, locale: String) -> Result<CourseTranslation, EzyTutorError>;
+}
+
+/// Persistence of tutors and their ratings. Soft deleted tutors are never returned, and
+/// reading, updating or deleting one fails with `NotFound`. Every write bumps the version of
+/// the tutor.
+#[async_trait]
+pub trait TutorRepository: Send + Sync {
+    /// One page of tutors, each with its cursor, see `dbaccess::tutor::get_tutors` for the
+    /// keyset semantics.
+    async fn get_tutors(&self, sort: TutorSort, name_prefix: Option<&str>, verified: Option<bool>, after: Option<&TutorCursor>, limit: i64) -> Result<Vec<(Tutor, TutorCursor)>, EzyTutorError>;
+
+    async fn by_id(&self, id: Uuid) -> Result<Tutor, EzyTutorError>;
+
+    async fn get_tutors_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Tutor>, EzyTutorError>;
+
+    /// Active tutors that may be the same person as a new one, see `dbaccess::duplicate::find_duplicates`.
+    async fn find_duplicates(&self, name: &str, pic_url: &str) -> Result<Vec<Tutor>, EzyTutorError>;
+
+    async fn create(&self, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError>;
+
+    async fn update(&self, update_data: CreateTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        self.update_with(id, Box::new(|current| {
+            expected.check(current.version)?;
+            Ok(update_data)
+        })).await
+    }
+
+    /// Updates a tutor from its current state, with nothing written in between. The slug is
+    /// regenerated when the name changes.
+    async fn update_with(&self, id: Uuid, changes: TutorChanges<'_>) -> Result<Tutor, EzyTutorError>;
+
+    /// Applies a validated merge patch: absent members are left untouched.
+    async fn patch(&self, patch: PatchTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>;
+
+    async fn soft_delete(&self, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>;
+
+    /// Records the rating of a student, replacing any previous one.
+    async fn rate(&self, tutor_id: Uuid, student_id: Uuid, rating: i16) -> Result<TutorRating, EzyTutorError>;
+
+    /// Owner of a current or former slug of a tutor or course.
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError>;
+}
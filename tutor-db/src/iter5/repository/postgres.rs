@@ -0,0 +1,177 @@
+use std::ops::{Deref, DerefMut};
+use async_trait::async_trait;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Postgres};
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::course::{Course, CourseTranslation, CreateCourseDto, UpsertCourseTranslationDto};
+use crate::models::course_tutor::CourseTutor;
+use crate::models::slug::SlugOwner;
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor, TutorCursor, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+use crate::repository::{CourseChanges, CourseRepository, TutorChanges, TutorRepository};
+
+/// Where a `PostgresRepository` runs its queries.
+enum Source<'c> {
+    Pool(PgPool),
+    /// The transaction of a `UnitOfWork`, taken by one query at a time.
+    Transaction(Mutex<&'c mut PgConnection>),
+}
+
+/// A connection checked out of a `Source` for a single `dbaccess` call.
+enum Conn<'r, 'c> {
+    Pooled(Box<PoolConnection<Postgres>>),
+    Shared(MutexGuard<'r, &'c mut PgConnection>),
+}
+
+impl Deref for Conn<'_, '_> {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            Conn::Pooled(conn) => conn,
+            Conn::Shared(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for Conn<'_, '_> {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            Conn::Pooled(conn) => conn,
+            Conn::Shared(conn) => conn,
+        }
+    }
+}
+
+/// The repositories on top of the sqlx queries of `dbaccess`, either on the pool or bound to
+/// the transaction of a unit of work.
+pub struct PostgresRepository<'c> {
+    source: Source<'c>,
+}
+
+impl PostgresRepository<'static> {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresRepository { source: Source::Pool(pool) }
+    }
+}
+
+impl<'c> PostgresRepository<'c> {
+    /// Runs every query on `conn`, usually the transaction of a `UnitOfWork`.
+    pub fn on(conn: &'c mut PgConnection) -> Self {
+        PostgresRepository { source: Source::Transaction(Mutex::new(conn)) }
+    }
+
+    async fn conn(&self) -> Result<Conn<'_, 'c>, EzyTutorError> {
+        Ok(match &self.source {
+            Source::Pool(pool) => Conn::Pooled(Box::new(pool.acquire().await?)),
+            Source::Transaction(conn) => Conn::Shared(conn.lock().await),
+        })
+    }
+
+    async fn find_slug_owner(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        dbaccess::slug::find(&mut *self.conn().await?, slug).await?
+            .owner()
+            .ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))
+    }
+}
+
+#[async_trait]
+impl CourseRepository for PostgresRepository<'_> {
+    async fn get_courses_by_tutor(&self, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError> {
+        dbaccess::course::get_courses_by_tutor(&mut *self.conn().await?, tutor_id).await
+    }
+
+    async fn get_courses_by_ids(&self, course_ids: &[Uuid]) -> Result<Vec<Course>, EzyTutorError> {
+        dbaccess::course::get_courses_by_ids(&mut *self.conn().await?, course_ids).await
+    }
+
+    async fn get_course(&self, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
+        dbaccess::course::get_course(&mut *self.conn().await?, tutor_id, course_id).await
+    }
+
+    async fn new_course(&self, course: CreateCourseDto) -> Result<Course, EzyTutorError> {
+        dbaccess::course::new_course(&mut *self.conn().await?, course).await
+    }
+
+    async fn update_course_with(&self, tutor_id: Uuid, course_id: Uuid, changes: CourseChanges<'_>) -> Result<Course, EzyTutorError> {
+        dbaccess::course::update_course_with(&mut *self.conn().await?, tutor_id, course_id, changes).await
+    }
+
+    async fn soft_delete_course(&self, tutor_id: Uuid, course_id: Uuid, expected: &ExpectedVersion) -> Result<Course, EzyTutorError> {
+        dbaccess::course::soft_delete_course(&mut *self.conn().await?, tutor_id, course_id, expected).await
+    }
+
+    async fn get_memberships(&self, tutor_ids: &[Uuid]) -> Result<Vec<CourseTutor>, EzyTutorError> {
+        dbaccess::course_tutor::get_memberships(&mut *self.conn().await?, tutor_ids).await
+    }
+
+    async fn role_of(&self, course_id: Uuid, tutor_id: Uuid) -> Result<Option<String>, EzyTutorError> {
+        dbaccess::course_tutor::role_of(&mut *self.conn().await?, course_id, tutor_id).await
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        self.find_slug_owner(slug).await
+    }
+
+    async fn get_translations(&self, course_id: Uuid) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        dbaccess::course_translation::get_translations(&mut *self.conn().await?, course_id).await
+    }
+
+    async fn get_translations_for_courses(&self, course_ids: &[Uuid], locales: &[String]) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        dbaccess::course_translation::get_translations_for_courses(&mut *self.conn().await?, course_ids, locales).await
+    }
+
+    async fn upsert_translation(&self, course_id: Uuid, locale: String, dto: UpsertCourseTranslationDto) -> Result<CourseTranslation, EzyTutorError> {
+        dbaccess::course_translation::upsert_translation(&mut *self.conn().await?, course_id, locale, dto).await
+    }
+
+    async fn delete_translation(&self, course_id: Uuid, locale: String) -> Result<CourseTranslation, EzyTutorError> {
+        dbaccess::course_translation::delete_translation(&mut *self.conn().await?, course_id, locale).await
+    }
+}
+
+#[async_trait]
+impl TutorRepository for PostgresRepository<'_> {
+    async fn get_tutors(&self, sort: TutorSort, name_prefix: Option<&str>, verified: Option<bool>, after: Option<&TutorCursor>, limit: i64) -> Result<Vec<(Tutor, TutorCursor)>, EzyTutorError> {
+        dbaccess::tutor::get_tutors(&mut *self.conn().await?, sort, name_prefix, verified, after, limit).await
+    }
+
+    async fn by_id(&self, id: Uuid) -> Result<Tutor, EzyTutorError> {
+        dbaccess::tutor::by_id(&mut *self.conn().await?, id).await
+    }
+
+    async fn get_tutors_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Tutor>, EzyTutorError> {
+        dbaccess::tutor::get_tutors_by_ids(&mut *self.conn().await?, ids).await
+    }
+
+    async fn find_duplicates(&self, name: &str, pic_url: &str) -> Result<Vec<Tutor>, EzyTutorError> {
+        dbaccess::duplicate::find_duplicates(&mut *self.conn().await?, name, pic_url).await
+    }
+
+    async fn create(&self, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError> {
+        dbaccess::tutor::create(&mut *self.conn().await?, dto).await
+    }
+
+    async fn update_with(&self, id: Uuid, changes: TutorChanges<'_>) -> Result<Tutor, EzyTutorError> {
+        dbaccess::tutor::update_with(&mut *self.conn().await?, id, changes).await
+    }
+
+    async fn patch(&self, patch: PatchTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        dbaccess::tutor::patch(&mut *self.conn().await?, patch, id, expected).await
+    }
+
+    async fn soft_delete(&self, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        dbaccess::tutor::soft_delete(&mut *self.conn().await?, id, expected).await
+    }
+
+    async fn rate(&self, tutor_id: Uuid, student_id: Uuid, rating: i16) -> Result<TutorRating, EzyTutorError> {
+        dbaccess::tutor::rate(&mut *self.conn().await?, tutor_id, student_id, rating).await
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        self.find_slug_owner(slug).await
+    }
+}
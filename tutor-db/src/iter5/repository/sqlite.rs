@@ -0,0 +1,632 @@
+use std::str::FromStr;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::{Sqlite, Transaction};
+use uuid::Uuid;
+use crate::dbaccess::slug::{base_slug, candidates};
+use crate::errors::EzyTutorError;
+use crate::markdown;
+use crate::models::course::{Course, CourseTranslation, CreateCourseDto, UpsertCourseTranslationDto};
+use crate::models::course_tutor::{CO_TUTOR, CourseTutor, OWNER};
+use crate::models::slug::{SlugOwner, SlugRecord};
+use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor, TutorCursor, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+use crate::repository::{CourseChanges, CourseRepository, likely_duplicates, TutorChanges, TutorRepository};
+
+const TUTOR_COLUMNS: &str = "id, name, pic_url, profile, created_at, updated_at, deleted_at, slug, profile_html, verified, version";
+const TRANSLATION_COLUMNS: &str = "course_id, locale, name, description, created_at, updated_at";
+const COURSE_COLUMNS: &str = "id, tutor_id, name, description, format, structure, duration, price, language, level, \
+    posted_time, created_at, updated_at, deleted_at, slug, description_html, version";
+
+/// A listed tutor with the average rating it was sorted by.
+#[derive(sqlx::FromRow)]
+struct RatedTutor {
+    #[sqlx(flatten)]
+    tutor: Tutor,
+    rating: f64,
+}
+
+/// Opens the SQLite file of `url`, creating it when missing.
+pub async fn connect(url: &str) -> Result<SqlitePool, EzyTutorError> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    Ok(SqlitePool::connect_with(options).await?)
+}
+
+/// The repositories on top of an SQLite database migrated with `schema::SQLITE_MIGRATOR`.
+/// SQLite has no row locks: updates start with a no-op write, which takes the database write
+/// lock for the rest of the transaction where Postgres would use `FOR UPDATE`. Versions are
+/// bumped by the updates themselves, there is no trigger. Names are
+/// lowercased by SQLite for ASCII letters only, which the name filter and sort inherit.
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteRepository { pool }
+    }
+}
+
+/// `?, ?, ...` for binding a list, SQLite has no arrays.
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Same rules as `dbaccess::slug::available_slug`.
+async fn available_slug(tx: &mut Transaction<'_, Sqlite>, owner: SlugOwner, name: &str) -> Result<String, EzyTutorError> {
+    let base = base_slug(owner, name);
+    let taken: Vec<SlugRecord> = sqlx::query_as(
+        "SELECT slug, tutor_id, course_id, created_at FROM slugs WHERE slug = ?1 OR slug LIKE ?1 || '-%'")
+        .bind(&base)
+        .fetch_all(&mut **tx).await?;
+
+    for candidate in candidates(base) {
+        match taken.iter().find(|record| record.slug == candidate) {
+            None => return Ok(candidate),
+            Some(record) if record.owner() == Some(owner) => return Ok(candidate),
+            Some(_) => {}
+        }
+    }
+    unreachable!("the candidate slugs are unbounded")
+}
+
+async fn record_slug(tx: &mut Transaction<'_, Sqlite>, owner: SlugOwner, slug: &str) -> Result<(), EzyTutorError> {
+    let (tutor_id, course_id) = match owner {
+        SlugOwner::Tutor(tutor_id) => (Some(tutor_id), None),
+        SlugOwner::Course(course_id) => (None, Some(course_id)),
+    };
+    sqlx::query("INSERT INTO slugs (slug, tutor_id, course_id, created_at) VALUES (?1, ?2, ?3, ?4) ON CONFLICT (slug) DO NOTHING")
+        .bind(slug)
+        .bind(tutor_id)
+        .bind(course_id)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut **tx).await?;
+
+    Ok(())
+}
+
+async fn find_slug_owner(pool: &SqlitePool, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+    let record: SlugRecord = sqlx::query_as("SELECT slug, tutor_id, course_id, created_at FROM slugs WHERE slug = ?1")
+        .bind(slug)
+        .fetch_one(pool).await?;
+    record.owner().ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))
+}
+
+#[async_trait]
+impl CourseRepository for SqliteRepository {
+    async fn get_courses_by_tutor(&self, tutor_id: Uuid) -> Result<Vec<Course>, EzyTutorError> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT {} FROM ezy_course_c4
+                WHERE id IN (SELECT course_id FROM course_tutors WHERE tutor_id = ?1) AND deleted_at is null
+                ORDER BY created_at, id", COURSE_COLUMNS))
+            .bind(tutor_id)
+            .fetch_all(&self.pool).await?)
+    }
+
+    async fn get_courses_by_ids(&self, course_ids: &[Uuid]) -> Result<Vec<Course>, EzyTutorError> {
+        let sql = format!("SELECT {} FROM ezy_course_c4 WHERE id IN ({}) AND deleted_at is null",
+                          COURSE_COLUMNS, placeholders(course_ids.len()));
+        let mut query = sqlx::query_as(&sql);
+        for course_id in course_ids {
+            query = query.bind(course_id);
+        }
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    async fn get_course(&self, tutor_id: Uuid, course_id: Uuid) -> Result<Course, EzyTutorError> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT {} FROM ezy_course_c4
+                WHERE id = ?2 AND id IN (SELECT course_id FROM course_tutors WHERE tutor_id = ?1) AND deleted_at is null",
+            COURSE_COLUMNS))
+            .bind(tutor_id)
+            .bind(course_id)
+            .fetch_one(&self.pool).await?)
+    }
+
+    async fn new_course(&self, course: CreateCourseDto) -> Result<Course, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        let register_time = Utc::now().naive_utc();
+        let id = Uuid::new_v4();
+        let slug = available_slug(&mut tx, SlugOwner::Course(id), &course.name).await?;
+        let inserted_course: Course = sqlx::query_as(&format!(
+            "INSERT INTO ezy_course_c4 (id, tutor_id, name, description, format, structure, duration, price, language, level,
+                posted_time, created_at, updated_at, slug, description_html)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11, ?11, ?12, ?13)
+                RETURNING {}", COURSE_COLUMNS))
+            .bind(id)
+            .bind(course.tutor_id)
+            .bind(&course.name)
+            .bind(&course.description)
+            .bind(course.format)
+            .bind(course.structure)
+            .bind(course.duration)
+            .bind(course.price)
+            .bind(course.language)
+            .bind(course.level)
+            .bind(register_time)
+            .bind(slug)
+            .bind(course.description.as_deref().map(markdown::to_html))
+            .fetch_one(&mut *tx).await?;
+        record_slug(&mut tx, SlugOwner::Course(id), &inserted_course.slug).await?;
+        sqlx::query("INSERT INTO course_tutors (course_id, tutor_id, role, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)")
+            .bind(id)
+            .bind(inserted_course.tutor_id)
+            .bind(OWNER)
+            .bind(register_time)
+            .execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(inserted_course)
+    }
+
+    async fn update_course_with(&self, tutor_id: Uuid, course_id: Uuid, changes: CourseChanges<'_>) -> Result<Course, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE ezy_course_c4 SET id = id WHERE id = ?1")
+            .bind(course_id)
+            .execute(&mut *tx).await?;
+        let current: Course = sqlx::query_as(&format!(
+            "SELECT {} FROM ezy_course_c4
+                WHERE id = ?2 AND deleted_at is null
+                    AND id IN (SELECT course_id FROM course_tutors WHERE tutor_id = ?1 AND role IN (?3, ?4))",
+            COURSE_COLUMNS))
+            .bind(tutor_id)
+            .bind(course_id)
+            .bind(OWNER)
+            .bind(CO_TUTOR)
+            .fetch_one(&mut *tx).await?;
+        let update_data = changes(&current)?;
+        let slug = if current.name == update_data.name {
+            current.slug
+        } else {
+            available_slug(&mut tx, SlugOwner::Course(course_id), &update_data.name).await?
+        };
+
+        let updated_course: Course = sqlx::query_as(&format!(
+            "UPDATE ezy_course_c4
+                SET name = ?1, description = ?2, format = ?3, structure = ?4, duration = ?5, price = ?6,
+                    language = ?7, level = ?8, slug = ?9, description_html = ?10, updated_at = ?11,
+                    version = version + 1
+                WHERE id = ?12 AND deleted_at is null
+                RETURNING {}", COURSE_COLUMNS))
+            .bind(update_data.name)
+            .bind(&update_data.description)
+            .bind(update_data.format)
+            .bind(update_data.structure)
+            .bind(update_data.duration)
+            .bind(update_data.price)
+            .bind(update_data.language)
+            .bind(update_data.level)
+            .bind(slug)
+            .bind(update_data.description.as_deref().map(markdown::to_html))
+            .bind(Utc::now().naive_utc())
+            .bind(course_id)
+            .fetch_one(&mut *tx).await?;
+        record_slug(&mut tx, SlugOwner::Course(course_id), &updated_course.slug).await?;
+        tx.commit().await?;
+
+        Ok(updated_course)
+    }
+
+    async fn soft_delete_course(&self, tutor_id: Uuid, course_id: Uuid, expected: &ExpectedVersion) -> Result<Course, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        let version: i32 = sqlx::query_scalar(
+            "UPDATE ezy_course_c4 SET id = id
+                WHERE tutor_id = ?1 AND id = ?2 AND deleted_at is null
+                RETURNING version")
+            .bind(tutor_id)
+            .bind(course_id)
+            .fetch_one(&mut *tx).await?;
+        expected.check(version)?;
+
+        let deleted_course = sqlx::query_as(&format!(
+            "UPDATE ezy_course_c4 SET deleted_at = ?1, updated_at = ?1, version = version + 1
+                WHERE id = ?2
+                RETURNING {}", COURSE_COLUMNS))
+            .bind(Utc::now().naive_utc())
+            .bind(course_id)
+            .fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(deleted_course)
+    }
+
+    async fn get_memberships(&self, tutor_ids: &[Uuid]) -> Result<Vec<CourseTutor>, EzyTutorError> {
+        let sql = format!(
+            "SELECT ct.course_id, ct.tutor_id, ct.role, ct.created_at, ct.updated_at
+                FROM course_tutors ct
+                JOIN ezy_course_c4 c ON c.id = ct.course_id
+                WHERE ct.tutor_id IN ({})
+                ORDER BY c.created_at, c.id", placeholders(tutor_ids.len()));
+        let mut query = sqlx::query_as(&sql);
+        for tutor_id in tutor_ids {
+            query = query.bind(tutor_id);
+        }
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    async fn role_of(&self, course_id: Uuid, tutor_id: Uuid) -> Result<Option<String>, EzyTutorError> {
+        Ok(sqlx::query_scalar(
+            "SELECT ct.role FROM course_tutors ct
+                JOIN ezy_course_c4 c ON c.id = ct.course_id AND c.deleted_at is null
+                WHERE ct.course_id = ?1 AND ct.tutor_id = ?2")
+            .bind(course_id)
+            .bind(tutor_id)
+            .fetch_optional(&self.pool).await?)
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        find_slug_owner(&self.pool, slug).await
+    }
+
+    async fn get_translations(&self, course_id: Uuid) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        Ok(sqlx::query_as(&format!(
+            "SELECT {} FROM course_translations WHERE course_id = ?1 ORDER BY locale", TRANSLATION_COLUMNS))
+            .bind(course_id)
+            .fetch_all(&self.pool).await?)
+    }
+
+    async fn get_translations_for_courses(&self, course_ids: &[Uuid], locales: &[String]) -> Result<Vec<CourseTranslation>, EzyTutorError> {
+        if course_ids.is_empty() || locales.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = format!("SELECT {} FROM course_translations WHERE course_id IN ({}) AND locale IN ({})",
+                          TRANSLATION_COLUMNS, placeholders(course_ids.len()), placeholders(locales.len()));
+        let mut query = sqlx::query_as(&sql);
+        for course_id in course_ids {
+            query = query.bind(course_id);
+        }
+        for locale in locales {
+            query = query.bind(locale);
+        }
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    async fn upsert_translation(&self, course_id: Uuid, locale: String, dto: UpsertCourseTranslationDto) -> Result<CourseTranslation, EzyTutorError> {
+        Ok(sqlx::query_as(&format!(
+            "INSERT INTO course_translations (course_id, locale, name, description, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+                ON CONFLICT (course_id, locale)
+                    DO UPDATE SET name = excluded.name, description = excluded.description, updated_at = excluded.updated_at
+                RETURNING {}", TRANSLATION_COLUMNS))
+            .bind(course_id)
+            .bind(locale)
+            .bind(dto.name)
+            .bind(dto.description)
+            .bind(Utc::now().naive_utc())
+            .fetch_one(&self.pool).await?)
+    }
+
+    async fn delete_translation(&self, course_id: Uuid, locale: String) -> Result<CourseTranslation, EzyTutorError> {
+        Ok(sqlx::query_as(&format!(
+            "DELETE FROM course_translations WHERE course_id = ?1 AND locale = ?2 RETURNING {}", TRANSLATION_COLUMNS))
+            .bind(course_id)
+            .bind(locale)
+            .fetch_one(&self.pool).await?)
+    }
+}
+
+#[async_trait]
+impl TutorRepository for SqliteRepository {
+    async fn get_tutors(&self, sort: TutorSort, name_prefix: Option<&str>, verified: Option<bool>, after: Option<&TutorCursor>, limit: i64) -> Result<Vec<(Tutor, TutorCursor)>, EzyTutorError> {
+        // Same keyset conditions as `dbaccess::tutor::get_tutors`
+        let (order, after_cursor) = match sort {
+            TutorSort::Created => ("created_at, id", "(created_at, id) > (?2, ?5)"),
+            TutorSort::Name => ("lower(name), id", "(lower(name), id) > (lower(?2), ?5)"),
+            TutorSort::Rating => ("rating DESC, id", "(rating < ?2 OR (rating = ?2 AND id > ?5))"),
+        };
+        let sql = format!(
+            "WITH rated AS (
+                SELECT t.*, coalesce((SELECT avg(r.rating) FROM tutor_ratings r WHERE r.tutor_id = t.id), 0.0) as rating
+                FROM tutors t
+            )
+            SELECT {}, rating FROM rated
+            WHERE deleted_at is null
+                AND (?1 is null OR substr(lower(name), 1, length(?1)) = lower(?1))
+                AND (?4 is null OR verified = ?4)
+                AND (?2 is null OR {})
+            ORDER BY {}
+            LIMIT ?3", TUTOR_COLUMNS, after_cursor, order);
+        let query = sqlx::query_as::<_, RatedTutor>(&sql).bind(name_prefix);
+        let query = match after {
+            None => query.bind(None::<String>).bind(limit).bind(verified).bind(None::<Uuid>),
+            Some(TutorCursor::Created { created_at, id }) => query.bind(created_at).bind(limit).bind(verified).bind(id),
+            Some(TutorCursor::Name { name, id }) => query.bind(name).bind(limit).bind(verified).bind(id),
+            Some(TutorCursor::Rating { rating, id }) => query.bind(rating).bind(limit).bind(verified).bind(id),
+        };
+        Ok(query.fetch_all(&self.pool).await?
+            .into_iter()
+            .map(|rated| {
+                let cursor = TutorCursor::of(sort, &rated.tutor, rated.rating);
+                (rated.tutor, cursor)
+            })
+            .collect())
+    }
+
+    async fn by_id(&self, id: Uuid) -> Result<Tutor, EzyTutorError> {
+        Ok(sqlx::query_as(&format!("SELECT {} FROM tutors WHERE id = ?1 AND deleted_at is null", TUTOR_COLUMNS))
+            .bind(id)
+            .fetch_one(&self.pool).await?)
+    }
+
+    async fn get_tutors_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Tutor>, EzyTutorError> {
+        let sql = format!("SELECT {} FROM tutors WHERE id IN ({}) AND deleted_at is null", TUTOR_COLUMNS, placeholders(ids.len()));
+        let mut query = sqlx::query_as(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.fetch_all(&self.pool).await?)
+    }
+
+    async fn find_duplicates(&self, name: &str, pic_url: &str) -> Result<Vec<Tutor>, EzyTutorError> {
+        let tutors = sqlx::query_as(&format!("SELECT {} FROM tutors WHERE deleted_at is null", TUTOR_COLUMNS))
+            .fetch_all(&self.pool).await?;
+        Ok(likely_duplicates(tutors, name, pic_url))
+    }
+
+    async fn create(&self, dto: CreateTutorDto) -> Result<Tutor, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        let id = Uuid::new_v4();
+        let slug = available_slug(&mut tx, SlugOwner::Tutor(id), &dto.name).await?;
+        let tutor: Tutor = sqlx::query_as(&format!(
+            "INSERT INTO tutors (id, name, pic_url, profile, created_at, updated_at, slug, profile_html)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7)
+                RETURNING {}", TUTOR_COLUMNS))
+            .bind(id)
+            .bind(dto.name)
+            .bind(dto.pic_url)
+            .bind(&dto.profile)
+            .bind(Utc::now().naive_utc())
+            .bind(slug)
+            .bind(markdown::to_html(&dto.profile))
+            .fetch_one(&mut *tx).await?;
+        record_slug(&mut tx, SlugOwner::Tutor(id), &tutor.slug).await?;
+        tx.commit().await?;
+
+        Ok(tutor)
+    }
+
+    async fn update_with(&self, id: Uuid, changes: TutorChanges<'_>) -> Result<Tutor, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE tutors SET id = id WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx).await?;
+        let current: Tutor = sqlx::query_as(&format!("SELECT {} FROM tutors WHERE id = ?1 AND deleted_at is null", TUTOR_COLUMNS))
+            .bind(id)
+            .fetch_one(&mut *tx).await?;
+        let update_data = changes(&current)?;
+        let slug = if current.name == update_data.name {
+            current.slug
+        } else {
+            available_slug(&mut tx, SlugOwner::Tutor(id), &update_data.name).await?
+        };
+
+        let tutor: Tutor = sqlx::query_as(&format!(
+            "UPDATE tutors
+                SET name = ?1, pic_url = ?2, profile = ?3, slug = ?4, profile_html = ?5, updated_at = ?6,
+                    version = version + 1
+                WHERE id = ?7 AND deleted_at is null
+                RETURNING {}", TUTOR_COLUMNS))
+            .bind(update_data.name)
+            .bind(update_data.pic_url)
+            .bind(&update_data.profile)
+            .bind(slug)
+            .bind(markdown::to_html(&update_data.profile))
+            .bind(Utc::now().naive_utc())
+            .bind(id)
+            .fetch_one(&mut *tx).await?;
+        record_slug(&mut tx, SlugOwner::Tutor(id), &tutor.slug).await?;
+        tx.commit().await?;
+
+        Ok(tutor)
+    }
+
+    async fn patch(&self, patch: PatchTutorDto, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        self.update_with(id, Box::new(|current| {
+            expected.check(current.version)?;
+            Ok(CreateTutorDto {
+                name: patch.name.flatten().unwrap_or_else(|| current.name.clone()),
+                pic_url: patch.pic_url.flatten().unwrap_or_else(|| current.pic_url.clone()),
+                profile: patch.profile.flatten().unwrap_or_else(|| current.profile.clone()),
+            })
+        })).await
+    }
+
+    async fn soft_delete(&self, id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError> {
+        let mut tx = self.pool.begin().await?;
+        let version: i32 = sqlx::query_scalar("UPDATE tutors SET id = id WHERE id = ?1 AND deleted_at is null RETURNING version")
+            .bind(id)
+            .fetch_one(&mut *tx).await?;
+        expected.check(version)?;
+
+        let tutor = sqlx::query_as(&format!(
+            "UPDATE tutors SET deleted_at = ?1, updated_at = ?1, version = version + 1
+                WHERE id = ?2
+                RETURNING {}", TUTOR_COLUMNS))
+            .bind(Utc::now().naive_utc())
+            .bind(id)
+            .fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(tutor)
+    }
+
+    async fn rate(&self, tutor_id: Uuid, student_id: Uuid, rating: i16) -> Result<TutorRating, EzyTutorError> {
+        Ok(sqlx::query_as(
+            "INSERT INTO tutor_ratings (tutor_id, student_id, rating, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?4)
+                ON CONFLICT (tutor_id, student_id)
+                    DO UPDATE SET rating = excluded.rating, updated_at = excluded.updated_at
+                RETURNING tutor_id, student_id, rating, created_at, updated_at")
+            .bind(tutor_id)
+            .bind(student_id)
+            .bind(rating)
+            .bind(Utc::now().naive_utc())
+            .fetch_one(&self.pool).await?)
+    }
+
+    async fn find_slug(&self, slug: &str) -> Result<SlugOwner, EzyTutorError> {
+        find_slug_owner(&self.pool, slug).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, web, App};
+    use actix_web::http::header::{ACCEPT_LANGUAGE, LOCATION};
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+    use crate::errors::EzyTutorError;
+    use crate::models::course::{CourseDetail, UpdateCourseDto};
+    use crate::models::tutor::TutorDetail;
+    use crate::routes::{course_routes, tutor_routes};
+    use crate::schema;
+    use crate::state::AppState;
+    use super::*;
+
+    #[actix_rt::test]
+    async fn courses_and_tutors_persist_on_sqlite() {
+        let pool = connect("sqlite::memory:").await.unwrap();
+        schema::prepare(&schema::SQLITE_MIGRATOR, &pool, true).await.unwrap();
+        let app_state = web::Data::new(AppState::sqlite("".to_string(), pool));
+
+        let mut tutors = vec![];
+        for name in ["Zoé", "ana", "Bruno"] {
+            tutors.push(app_state.tutors.create(CreateTutorDto {
+                name: name.into(),
+                pic_url: "https://i.pravatar.cc/150".into(),
+                profile: "Some *profile*".into(),
+            }).await.unwrap());
+        }
+        assert_eq!(tutors[0].slug, "zoe");
+        assert_eq!(tutors[0].profile_html, "<p>Some <em>profile</em></p>\n");
+        let page = app_state.tutors.get_tutors(TutorSort::Name, None, None, None, 2).await.unwrap();
+        let names: Vec<&str> = page.iter().map(|(tutor, _)| tutor.name.as_str()).collect();
+        assert_eq!(names, vec!["ana", "Bruno"]);
+        let page = app_state.tutors.get_tutors(TutorSort::Name, None, None, Some(&page[1].1), 2).await.unwrap();
+        assert_eq!(page, vec![(tutors[0].clone(), TutorCursor::Name { name: "Zoé".into(), id: tutors[0].id })]);
+
+        let tutor = &tutors[1];
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: tutor.id,
+            name: "Año de Óptica".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: Some(100),
+            language: None,
+            level: None,
+        }).await.unwrap();
+        assert_eq!(course.slug, "ano-de-optica");
+        assert_eq!(app_state.courses.role_of(course.id, tutor.id).await.unwrap().as_deref(), Some(OWNER));
+
+        let renamed = app_state.courses.update_course(tutor.id, course.id, UpdateCourseDto {
+            name: "Óptica avanzada".into(),
+            description: Some("Lenses".into()),
+            format: None,
+            structure: None,
+            duration: None,
+            price: Some(120),
+            language: None,
+            level: None,
+        }, &ExpectedVersion::Versions(vec![course.version])).await.unwrap();
+        assert_eq!(renamed.slug, "optica-avanzada");
+        assert!(renamed.updated_at > course.updated_at);
+        assert_eq!(renamed.version, course.version + 1);
+        let stale = app_state.courses.soft_delete_course(tutor.id, course.id, &ExpectedVersion::Versions(vec![course.version])).await;
+        assert!(matches!(stale, Err(EzyTutorError::PreconditionFailed(_))));
+        assert_eq!(app_state.courses.get_courses_by_tutor(tutor.id).await.unwrap(), vec![renamed.clone()]);
+        let memberships = app_state.courses.get_memberships(&[tutor.id]).await.unwrap();
+        assert_eq!(memberships.len(), 1);
+
+        let wrong_tutor = app_state.courses.update_course(tutors[0].id, course.id, UpdateCourseDto {
+            name: "Stolen".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }, &ExpectedVersion::Any).await;
+        assert!(matches!(wrong_tutor, Err(EzyTutorError::NotFound(_))));
+
+        app_state.courses.soft_delete_course(tutor.id, course.id, &ExpectedVersion::Any).await.unwrap();
+        let deleted = app_state.courses.get_course(tutor.id, course.id).await;
+        assert!(matches!(deleted, Err(EzyTutorError::NotFound(_))));
+
+        let patch: PatchTutorDto = serde_json::from_str(r#"{"profile": "Other profile"}"#).unwrap();
+        let patched = app_state.tutors.patch(patch, tutor.id, &ExpectedVersion::Any).await.unwrap();
+        assert_eq!((patched.name.as_str(), patched.profile.as_str()), ("ana", "Other profile"));
+        app_state.tutors.rate(tutors[2].id, Uuid::new_v4(), 5).await.unwrap();
+        let page = app_state.tutors.get_tutors(TutorSort::Rating, None, None, None, 1).await.unwrap();
+        assert_eq!(page[0].1, TutorCursor::Rating { rating: 5.0, id: tutors[2].id });
+        let page = app_state.tutors.get_tutors(TutorSort::Rating, None, None, Some(&page[0].1), 1).await.unwrap();
+        assert_eq!(page[0].1.id(), tutors[0].id.min(tutors[1].id));
+
+        app_state.tutors.soft_delete(tutor.id, &ExpectedVersion::Any).await.unwrap();
+        let deleted = app_state.tutors.by_id(tutor.id).await;
+        assert!(matches!(deleted, Err(EzyTutorError::NotFound(_))));
+    }
+
+    #[actix_rt::test]
+    async fn localized_and_slug_routes_work_on_sqlite() {
+        let pool = connect("sqlite::memory:").await.unwrap();
+        schema::prepare(&schema::SQLITE_MIGRATOR, &pool, true).await.unwrap();
+        let app_state = web::Data::new(AppState::sqlite("".to_string(), pool));
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(course_routes).configure(tutor_routes)).await;
+
+        let tutor = app_state.tutors.create(CreateTutorDto {
+            name: "Ana".into(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }).await.unwrap();
+        let course = app_state.courses.new_course(CreateCourseDto {
+            tutor_id: tutor.id,
+            name: "Óptica".into(),
+            description: Some("Lentes".into()),
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        }).await.unwrap();
+        let req = test::TestRequest::put()
+            .uri(&format!("/courses/{}/{}/translations/en", tutor.id, course.id))
+            .set_json(json!({ "name": "Optics", "description": "*Lenses*" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get().uri(&format!("/courses/{}", tutor.id))
+            .insert_header((ACCEPT_LANGUAGE, "en-US,en;q=0.9")).to_request();
+        let courses: Vec<CourseDetail> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(courses[0].course.name, "Optics");
+
+        let req = test::TestRequest::get().uri("/courses/by-slug/optica")
+            .insert_header((ACCEPT_LANGUAGE, "en-US")).to_request();
+        let detail: CourseDetail = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail.course.description_html.as_deref(), Some("<p><em>Lenses</em></p>\n"));
+        let req = test::TestRequest::get().uri("/courses/by-slug/optica")
+            .insert_header((ACCEPT_LANGUAGE, "fr")).to_request();
+        let detail: CourseDetail = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail.course.name, "Óptica");
+
+        let req = test::TestRequest::get().uri("/tutors/by-slug/ana?include=courses")
+            .insert_header((ACCEPT_LANGUAGE, "en-US")).to_request();
+        let detail: TutorDetail = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail.courses.unwrap()[0].name, "Optics");
+        app_state.tutors.update(CreateTutorDto {
+            name: "Ana María".into(),
+            pic_url: tutor.pic_url.clone(),
+            profile: tutor.profile.clone(),
+        }, tutor.id, &ExpectedVersion::Any).await.unwrap();
+        let req = test::TestRequest::get().uri("/tutors/by-slug/ana").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), "ana-maria");
+        let req = test::TestRequest::get().uri("/tutors/by-slug/nobody").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+}
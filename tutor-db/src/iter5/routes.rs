@@ -1,5 +1,6 @@
-use actix_web::web;
-use crate::handlers::{course::*, general::*, tutor::*};
+use actix_web::{guard, web};
+use crate::handlers::{booking::*, coupon::*, course::*, course_tag::*, course_translation::*, course_tutor::*, credential::*, enrollment::*, general::*, learning_path::*, recommendation::*, tutor::*};
+use crate::patch::is_json_patch;
 
 pub fn general_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/health", web::get().to(health_check_handler));
@@ -8,10 +9,31 @@ pub fn general_routes(cfg: &mut web::ServiceConfig) {
 pub fn course_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/courses")
         .route("/", web::post().to(new_course))
+        .route("/by-slug/{slug}", web::get().to(get_course_by_slug))
         .route("/{tutor_id}", web::get().to(get_courses_for_tutor))
         .route("/{tutor_id}/{course_id}", web::get().to(get_course_detail))
         .route("/{tutor_id}/{course_id}", web::put().to(update_course_detail))
+        .route("/{tutor_id}/{course_id}", web::patch().guard(guard::fn_guard(is_json_patch)).to(json_patch_course_detail))
         .route("/{tutor_id}/{course_id}", web::delete().to(soft_delete_course))
+        .route("/{tutor_id}/{course_id}/translations", web::get().to(get_course_translations))
+        .route("/{tutor_id}/{course_id}/translations/{locale}", web::put().to(upsert_course_translation))
+        .route("/{tutor_id}/{course_id}/translations/{locale}", web::delete().to(delete_course_translation))
+        .route("/{tutor_id}/{course_id}/capacity", web::get().to(get_course_capacity))
+        .route("/{tutor_id}/{course_id}/capacity", web::put().to(set_course_capacity))
+        .route("/{tutor_id}/{course_id}/enrollments", web::post().to(enroll_student))
+        .route("/{tutor_id}/{course_id}/enrollments/{student_id}", web::delete().to(withdraw_student))
+        .route("/{tutor_id}/{course_id}/waitlist", web::get().to(get_course_waitlist))
+        .route("/{tutor_id}/{course_id}/waitlist", web::post().to(join_course_waitlist))
+        .route("/{tutor_id}/{course_id}/waitlist/{student_id}", web::get().to(get_waitlist_position))
+        .route("/{tutor_id}/{course_id}/waitlist/{student_id}", web::delete().to(leave_course_waitlist))
+        .route("/{tutor_id}/{course_id}/quote", web::get().to(get_course_quote))
+        .route("/{tutor_id}/{course_id}/redemptions", web::post().to(redeem_coupon))
+        .route("/{tutor_id}/{course_id}/tags", web::get().to(get_course_tags))
+        .route("/{tutor_id}/{course_id}/tags", web::put().to(set_course_tags))
+        .route("/{tutor_id}/{course_id}/similar", web::get().to(get_similar_courses))
+        .route("/{tutor_id}/{course_id}/tutors", web::get().to(get_course_tutors))
+        .route("/{tutor_id}/{course_id}/tutors/{member_id}", web::put().to(set_course_tutor))
+        .route("/{tutor_id}/{course_id}/tutors/{member_id}", web::delete().to(remove_course_tutor))
     );
 }
 
@@ -19,9 +41,42 @@ pub fn tutor_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/tutors")
         .route("/", web::post().to(new_tutor))
         .route("/", web::get().to(get_tutors))
+        .route("/by-slug/{slug}", web::get().to(get_tutor_by_slug))
+        .route("/duplicates", web::get().to(get_possible_duplicates))
+        .route("/credentials/pending", web::get().to(get_pending_credentials))
         .route("/{tutor_id}", web::get().to(get_tutor_by_id))
         .route("/{tutor_id}", web::put().to(update_tutor_detail))
+        .route("/{tutor_id}", web::patch().guard(guard::fn_guard(is_json_patch)).to(json_patch_tutor_detail))
         .route("/{tutor_id}", web::patch().to(partially_update_tutor_detail))
         .route("/{tutor_id}", web::delete().to(soft_delete_tutor))
+        .route("/{tutor_id}/coupons", web::get().to(get_tutor_coupons))
+        .route("/{tutor_id}/coupons", web::post().to(new_coupon))
+        .route("/{tutor_id}/coupons/{coupon_id}", web::delete().to(delete_coupon))
+        .route("/{tutor_id}/credentials", web::get().to(get_tutor_credentials))
+        .route("/{tutor_id}/credentials", web::post().to(new_credential))
+        .route("/{tutor_id}/credentials/{credential_id}", web::delete().to(delete_credential))
+        .route("/{tutor_id}/credentials/{credential_id}/review", web::put().to(review_credential))
+        .route("/{tutor_id}/merge", web::post().to(merge_tutor))
+        .route("/{tutor_id}/ratings/{student_id}", web::put().to(rate_tutor))
+        .route("/{tutor_id}/stats", web::get().to(get_tutor_stats))
+        .route("/{tutor_id}/availability", web::get().to(get_tutor_availability))
+        .route("/{tutor_id}/availability", web::put().to(set_tutor_availability))
+        .route("/{tutor_id}/availability/exceptions", web::get().to(get_availability_exceptions))
+        .route("/{tutor_id}/availability/exceptions", web::post().to(new_availability_exception))
+        .route("/{tutor_id}/availability/exceptions/{exception_id}", web::delete().to(delete_availability_exception))
+        .route("/{tutor_id}/slots", web::get().to(get_open_slots))
+        .route("/{tutor_id}/lessons", web::get().to(get_tutor_lessons))
+        .route("/{tutor_id}/lessons", web::post().to(book_lesson))
+        .route("/{tutor_id}/lessons/{lesson_id}", web::delete().to(cancel_lesson))
+    );
+}
+
+pub fn path_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/paths")
+        .route("/", web::post().to(new_path))
+        .route("/", web::get().to(get_paths))
+        .route("/{path_id}", web::get().to(get_path_by_id))
+        .route("/{path_id}", web::put().to(update_path))
+        .route("/{path_id}", web::delete().to(soft_delete_path))
     );
 }
\ No newline at end of file
@@ -0,0 +1,342 @@
+use log::{info, warn};
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::{Acquire, Database, Executor, PgPool, Pool, Postgres, Transaction};
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::slug::SlugOwner;
+
+/// The versioned migrations of `src/iter5/migrations`, embedded in the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!("src/iter5/migrations");
+/// The versioned migrations of `src/iter5/sqlite_migrations`, for the `sqlite` feature.
+#[cfg(feature = "sqlite")]
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("src/iter5/sqlite_migrations");
+
+/// `migrate` subcommand of the server binary.
+#[derive(Debug, PartialEq)]
+pub enum MigrateCommand {
+    /// Applies every pending migration.
+    Up,
+    /// Reverts the migrations after the given version, or only the last one.
+    Down(Option<i64>),
+    /// Lists the migrations and whether they are applied.
+    Status,
+    /// Upgrades a Postgres database created by the old `dbscripts` to the schema of the initial
+    /// migration and records that migration as applied.
+    Baseline,
+}
+
+impl MigrateCommand {
+    pub fn parse(args: &[String]) -> Result<MigrateCommand, EzyTutorError> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            [] | ["up"] => Ok(MigrateCommand::Up),
+            ["down"] => Ok(MigrateCommand::Down(None)),
+            ["down", target] => target.parse()
+                .map(|target| MigrateCommand::Down(Some(target)))
+                .map_err(|_| EzyTutorError::InvalidInput(format!("Invalid migration version {}", target))),
+            ["status"] => Ok(MigrateCommand::Status),
+            ["baseline"] => Ok(MigrateCommand::Baseline),
+            _ => Err(EzyTutorError::InvalidInput("Usage: migrate [up | down [VERSION] | status | baseline]".to_string())),
+        }
+    }
+}
+
+/// Applied versions the binary does not know about, left by a newer release.
+fn unknown_versions(applied: &[i64], known: &[i64]) -> Vec<i64> {
+    applied.iter().filter(|version| !known.contains(version)).copied().collect()
+}
+
+fn known_versions(migrator: &Migrator) -> Vec<i64> {
+    migrator.iter().filter(|migration| !migration.migration_type.is_down_migration()).map(|migration| migration.version).collect()
+}
+
+async fn applied_versions<DB>(pool: &Pool<DB>) -> Result<Vec<i64>, EzyTutorError>
+    where DB: Database, DB::Connection: Migrate {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    Ok(conn.list_applied_migrations().await?.into_iter().map(|migration| migration.version).collect())
+}
+
+async fn check_not_ahead<DB>(migrator: &Migrator, pool: &Pool<DB>) -> Result<Vec<i64>, EzyTutorError>
+    where DB: Database, DB::Connection: Migrate {
+    let applied = applied_versions(pool).await?;
+    let unknown = unknown_versions(&applied, &known_versions(migrator));
+    if !unknown.is_empty() {
+        return Err(EzyTutorError::DBError(format!("Database schema is ahead of this binary, unknown migrations {:?}", unknown)));
+    }
+    Ok(applied)
+}
+
+/// Whether the database has the tables of the old `dbscripts` but no migration recorded.
+async fn is_legacy(pool: &PgPool) -> Result<bool, EzyTutorError> {
+    let applied = applied_versions(pool).await?;
+    let (has_tutors,): (bool,) = sqlx::query_as("SELECT to_regclass('tutors') IS NOT NULL").fetch_one(pool).await?;
+    Ok(applied.is_empty() && has_tutors)
+}
+
+/// Refuses a database created by the old `dbscripts` until it is baselined, as applying the
+/// initial migration over its tables would fail half way.
+pub async fn check_baselined(pool: &PgPool) -> Result<(), EzyTutorError> {
+    if is_legacy(pool).await? {
+        return Err(EzyTutorError::DBError("Database was created by the old dbscripts, run the migrate baseline subcommand first".to_string()));
+    }
+    Ok(())
+}
+
+/// Adds what the old `dbscripts` did not create, see the file.
+const UPGRADE: &str = include_str!("dbscripts/upgrade.sql");
+
+/// Run by `baseline` once every tutor and course has a slug.
+const REQUIRE_SLUGS: &str = r#"
+    ALTER TABLE tutors ALTER COLUMN slug SET NOT NULL;
+    ALTER TABLE ezy_course_c4 ALTER COLUMN slug SET NOT NULL;
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT FROM pg_constraint WHERE conname = 'tutors_slug_key') THEN
+            ALTER TABLE tutors ADD CONSTRAINT tutors_slug_key UNIQUE (slug);
+        END IF;
+        IF NOT EXISTS (SELECT FROM pg_constraint WHERE conname = 'ezy_course_c4_slug_key') THEN
+            ALTER TABLE ezy_course_c4 ADD CONSTRAINT ezy_course_c4_slug_key UNIQUE (slug);
+        END IF;
+    END
+    $$;
+"#;
+
+/// Upgrades a database created by the old `dbscripts` to the schema of the first migration of
+/// `migrator`, then records that migration as applied without running it. Later migrations stay
+/// pending. Nothing is changed when the upgraded schema still lacks a table or column of that
+/// migration.
+pub async fn baseline(migrator: &Migrator, pool: &PgPool) -> Result<(), EzyTutorError> {
+    if !is_legacy(pool).await? {
+        return Err(EzyTutorError::InvalidInput("Only a database created by the old dbscripts, with no migration applied, can be baselined".to_string()));
+    }
+    let initial = migrator.iter()
+        .find(|migration| !migration.migration_type.is_down_migration())
+        .ok_or(EzyTutorError::DBError("There are no migrations to baseline with".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+    tx.execute(UPGRADE).await?;
+    let tutors: Vec<(Uuid, String)> = sqlx::query_as("SELECT id, name FROM tutors WHERE slug IS NULL ORDER BY created_at, id")
+        .fetch_all(&mut *tx).await?;
+    for (id, name) in tutors {
+        let slug = assign_slug(&mut tx, SlugOwner::Tutor(id), &name).await?;
+        sqlx::query("UPDATE tutors SET slug = $1 WHERE id = $2").bind(slug).bind(id).execute(&mut *tx).await?;
+    }
+    let courses: Vec<(Uuid, String)> = sqlx::query_as("SELECT id, name FROM ezy_course_c4 WHERE slug IS NULL ORDER BY created_at, id")
+        .fetch_all(&mut *tx).await?;
+    for (id, name) in courses {
+        let slug = assign_slug(&mut tx, SlugOwner::Course(id), &name).await?;
+        sqlx::query("UPDATE ezy_course_c4 SET slug = $1 WHERE id = $2").bind(slug).bind(id).execute(&mut *tx).await?;
+    }
+    tx.execute(REQUIRE_SLUGS).await?;
+    dbaccess::tutor::render_profiles(&mut *tx).await?;
+    dbaccess::course::render_descriptions(&mut *tx).await?;
+
+    let missing = missing_columns(&mut tx, &initial.sql).await?;
+    if !missing.is_empty() {
+        return Err(EzyTutorError::DBError(format!("Database can not be baselined, it still lacks {} of the initial migration", missing.join(", "))));
+    }
+    sqlx::query("INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES ($1, $2, TRUE, $3, 0)")
+        .bind(initial.version)
+        .bind(initial.description.as_ref())
+        .bind(initial.checksum.as_ref())
+        .execute(&mut *tx).await?;
+    tx.commit().await?;
+    info!("Upgraded the database and recorded migration {} as applied", initial.version);
+    Ok(())
+}
+
+async fn assign_slug(tx: &mut Transaction<'_, Postgres>, owner: SlugOwner, name: &str) -> Result<String, EzyTutorError> {
+    let slug = dbaccess::slug::available_slug(tx, owner, name).await?;
+    dbaccess::slug::record_slug(tx, owner, &slug).await?;
+    Ok(slug)
+}
+
+/// The `table.column`s that `initial_sql` creates and the current schema does not have. The
+/// migration is run in a scratch schema, dropped again with the savepoint it is run in.
+async fn missing_columns(tx: &mut Transaction<'_, Postgres>, initial_sql: &str) -> Result<Vec<String>, EzyTutorError> {
+    let mut scratch = tx.begin().await?;
+    let (schema,): (String,) = sqlx::query_as("SELECT current_schema()").fetch_one(&mut *scratch).await?;
+    scratch.execute("CREATE SCHEMA baseline_check").await?;
+    sqlx::query("SELECT set_config('search_path', 'baseline_check, ' || quote_ident($1), true)")
+        .bind(&schema)
+        .execute(&mut *scratch).await?;
+    scratch.execute(initial_sql).await?;
+    let missing: Vec<(String,)> = sqlx::query_as(r#"
+        SELECT expected.table_name || '.' || expected.column_name
+            FROM information_schema.columns expected
+            WHERE expected.table_schema = 'baseline_check'
+                AND NOT EXISTS (SELECT FROM information_schema.columns actual
+                    WHERE actual.table_schema = $1
+                        AND actual.table_name = expected.table_name
+                        AND actual.column_name = expected.column_name)
+            ORDER BY expected.table_name, expected.ordinal_position"#)
+        .bind(schema)
+        .fetch_all(&mut *scratch).await?;
+    scratch.rollback().await?;
+    Ok(missing.into_iter().map(|(column,)| column).collect())
+}
+
+/// Run before serving: refuses a schema ahead of the binary, then applies the pending
+/// migrations when `apply_pending` is set or warns about them otherwise.
+pub async fn prepare<DB>(migrator: &Migrator, pool: &Pool<DB>, apply_pending: bool) -> Result<(), EzyTutorError>
+    where DB: Database, DB::Connection: Migrate {
+    let applied = check_not_ahead(migrator, pool).await?;
+    let pending = unknown_versions(&known_versions(migrator), &applied);
+    if pending.is_empty() {
+        return Ok(());
+    }
+    if apply_pending {
+        info!("Applying migrations {:?}", pending);
+        migrator.run(pool).await?;
+    } else {
+        warn!("Migrations {:?} are pending, run the migrate subcommand", pending);
+    }
+    Ok(())
+}
+
+pub async fn migrate<DB>(migrator: &Migrator, pool: &Pool<DB>, command: MigrateCommand) -> Result<(), EzyTutorError>
+    where DB: Database, DB::Connection: Migrate {
+    let applied = check_not_ahead(migrator, pool).await?;
+    match command {
+        MigrateCommand::Up => migrator.run(pool).await?,
+        MigrateCommand::Down(target) => {
+            let target = target.unwrap_or_else(|| {
+                let mut applied = applied.clone();
+                applied.sort();
+                applied.iter().rev().nth(1).copied().unwrap_or(0)
+            });
+            migrator.undo(pool, target).await?
+        }
+        MigrateCommand::Status => {
+            for migration in migrator.iter().filter(|migration| !migration.migration_type.is_down_migration()) {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{} {} {}", migration.version, state, migration.description);
+            }
+        }
+        MigrateCommand::Baseline => {
+            return Err(EzyTutorError::InvalidInput("Only Postgres databases can be baselined".to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+    use dotenv::dotenv;
+    use sqlx::Executor;
+    use sqlx::postgres::PgConnectOptions;
+    use uuid::Uuid;
+    use super::*;
+
+    #[test]
+    fn newer_migrations_are_unknown() {
+        assert_eq!(unknown_versions(&[1, 2, 3], &[1, 2]), vec![3]);
+        assert!(unknown_versions(&[1], &[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn migrate_subcommand_is_parsed() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(MigrateCommand::parse(&args(&[])).unwrap(), MigrateCommand::Up);
+        assert_eq!(MigrateCommand::parse(&args(&["down"])).unwrap(), MigrateCommand::Down(None));
+        assert_eq!(MigrateCommand::parse(&args(&["down", "20231101000000"])).unwrap(), MigrateCommand::Down(Some(20231101000000)));
+        assert!(MigrateCommand::parse(&args(&["down", "latest"])).is_err());
+        assert_eq!(MigrateCommand::parse(&args(&["baseline"])).unwrap(), MigrateCommand::Baseline);
+        assert!(MigrateCommand::parse(&args(&["sideways"])).is_err());
+    }
+
+    /// The tables of the old `dbscripts/tutor-course.sql`, as the databases created with it have
+    /// them, with a few rows.
+    const LEGACY_SCHEMA: &str = r#"
+        create table tutors
+        (
+            id         uuid UNIQUE  NOT NULL,
+            name       varchar(200) NOT NULL,
+            pic_url    varchar(200) NOT NULL,
+            profile    text         NOT NULL,
+            created_at timestamp    NOT NULL default now(),
+            updated_at timestamp    NOT NULL default now(),
+            deleted_at timestamp,
+            PRIMARY KEY (id, created_at, updated_at),
+            UNIQUE (id, created_at, updated_at)
+        );
+
+        create table ezy_course_c4
+        (
+            id          uuid UNIQUE  NOT NULL,
+            tutor_id    uuid         NOT NULL,
+            name        varchar(140) NOT NULL,
+            description text,
+            format      varchar(30),
+            structure   varchar(30),
+            duration    varchar(30),
+            price       INT,
+            language    varchar(30),
+            level       varchar(30),
+            posted_time timestamp    NOT NULL default now(),
+            created_at  timestamp    NOT NULL default now(),
+            updated_at  timestamp    NOT NULL default now(),
+            deleted_at  timestamp,
+            PRIMARY KEY (id, created_at, updated_at),
+            UNIQUE (id, tutor_id, created_at, updated_at),
+            CONSTRAINT fk_tutor
+                FOREIGN KEY (tutor_id)
+                    REFERENCES tutors (id)
+                    ON DELETE cascade
+        );
+
+        insert into tutors (id, name, pic_url, profile, created_at)
+        values ('d709c2c9-eeb8-4b6b-a63d-25ef38c78e61', 'Rogerio Bacon', 'https://i.pravatar.cc/150', 'Doctor **Mirabilis**', '2020-03-10 14:25:50'),
+               ('d709c2c9-eeb8-4b6b-a63d-25ef38c78e62', 'Rogerio Bacon', 'https://i.pravatar.cc/150', 'A namesake', '2020-03-11 14:25:50');
+        insert into ezy_course_c4 (id, tutor_id, name, description)
+        values ('70c57639-680a-44e8-a15b-e879d38aa856', 'd709c2c9-eeb8-4b6b-a63d-25ef38c78e61', 'Rudimentos de la óptica', '3 o 4 *cosas*');
+    "#;
+
+    #[actix_rt::test]
+    async fn legacy_database_is_baselined() {
+        dotenv().ok();
+
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let admin_pool = PgPool::connect(&database_url).await.unwrap();
+        let name = format!("legacy_{}", Uuid::new_v4().simple());
+        admin_pool.execute(format!("CREATE DATABASE {}", name).as_str()).await.unwrap();
+        let options = PgConnectOptions::from_str(&database_url).unwrap().database(&name);
+        let pool = PgPool::connect_with(options).await.unwrap();
+        pool.execute(LEGACY_SCHEMA).await.unwrap();
+        assert!(check_baselined(&pool).await.is_err());
+
+        // A column the upgrade can not add is reported, and nothing is changed
+        pool.execute("ALTER TABLE tutors RENAME COLUMN pic_url TO picture").await.unwrap();
+        let err = baseline(&MIGRATOR, &pool).await.unwrap_err();
+        assert!(matches!(&err, EzyTutorError::DBError(msg) if msg.contains("tutors.pic_url")), "{:?}", err);
+        assert!(check_baselined(&pool).await.is_err());
+        let (slug_added,): (bool,) = sqlx::query_as("SELECT EXISTS (SELECT FROM information_schema.columns WHERE table_name = 'tutors' AND column_name = 'slug')")
+            .fetch_one(&pool).await.unwrap();
+        assert!(!slug_added);
+        pool.execute("ALTER TABLE tutors RENAME COLUMN picture TO pic_url").await.unwrap();
+
+        baseline(&MIGRATOR, &pool).await.unwrap();
+        check_baselined(&pool).await.unwrap();
+        assert_eq!(applied_versions(&pool).await.unwrap(), vec![20231101000000]);
+        prepare(&MIGRATOR, &pool, true).await.unwrap();
+        assert_eq!(applied_versions(&pool).await.unwrap(), known_versions(&MIGRATOR));
+        assert!(baseline(&MIGRATOR, &pool).await.is_err());
+
+        let tutor_id = Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e61").unwrap();
+        let tutor = dbaccess::tutor::by_id(&pool, tutor_id).await.unwrap();
+        assert_eq!(tutor.slug, "rogerio-bacon");
+        assert_eq!(tutor.profile_html, "<p>Doctor <strong>Mirabilis</strong></p>\n");
+        let namesake = dbaccess::tutor::by_id(&pool, Uuid::from_str("d709c2c9-eeb8-4b6b-a63d-25ef38c78e62").unwrap()).await.unwrap();
+        assert_eq!(namesake.slug, "rogerio-bacon-2");
+        let course = dbaccess::course::get_course(&pool, tutor_id, Uuid::from_str("70c57639-680a-44e8-a15b-e879d38aa856").unwrap()).await.unwrap();
+        assert_eq!(course.slug, "rudimentos-de-la-optica");
+        assert_eq!(course.description_html.as_deref(), Some("<p>3 o 4 <em>cosas</em></p>\n"));
+        assert!(dbaccess::slug::find(&pool, "rogerio-bacon-2").await.is_ok());
+
+        pool.close().await;
+        admin_pool.execute(format!("DROP DATABASE {} WITH (FORCE)", name).as_str()).await.unwrap();
+    }
+}
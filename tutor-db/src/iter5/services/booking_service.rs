@@ -0,0 +1,84 @@
+use actix_web::web;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::booking::{AvailabilityException, AvailabilityExceptionDto, AvailabilityRule, BookLessonDto, CANCELLATION_WINDOW_HOURS,
+                             DEFAULT_LESSON_MINUTES, Lesson, MAX_SLOT_RANGE_DAYS, OpenSlots, SlotQuery, validate_lesson_minutes, WeeklyAvailabilityDto};
+use crate::services::unit_of_work::UnitOfWork;
+use crate::state::AppState;
+
+async fn known_timezone(app_state: &web::Data<AppState>, timezone: &str) -> Result<(), EzyTutorError> {
+    if !dbaccess::booking::is_known_timezone(&app_state.db, timezone).await? {
+        return Err(EzyTutorError::InvalidInput(format!("Unknown timezone {}", timezone)));
+    }
+    Ok(())
+}
+
+pub async fn get_availability(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Vec<AvailabilityRule>, EzyTutorError> {
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::booking::get_rules(&app_state.db, tutor_id).await
+}
+
+pub async fn set_availability(app_state: web::Data<AppState>, tutor_id: Uuid, availability_dto: WeeklyAvailabilityDto) -> Result<Vec<AvailabilityRule>, EzyTutorError> {
+    availability_dto.validate()?;
+    known_timezone(&app_state, &availability_dto.timezone).await?;
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::booking::replace_rules(&app_state.db, tutor_id, availability_dto).await
+}
+
+pub async fn get_exceptions(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Vec<AvailabilityException>, EzyTutorError> {
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::booking::get_exceptions(&app_state.db, tutor_id).await
+}
+
+pub async fn add_exception(app_state: web::Data<AppState>, tutor_id: Uuid, exception_dto: AvailabilityExceptionDto) -> Result<AvailabilityException, EzyTutorError> {
+    exception_dto.validate()?;
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::booking::add_exception(&app_state.db, tutor_id, exception_dto).await
+}
+
+pub async fn delete_exception(app_state: web::Data<AppState>, tutor_id: Uuid, exception_id: Uuid) -> Result<AvailabilityException, EzyTutorError> {
+    dbaccess::booking::delete_exception(&app_state.db, tutor_id, exception_id).await
+}
+
+pub async fn get_open_slots(app_state: web::Data<AppState>, tutor_id: Uuid, query: SlotQuery) -> Result<OpenSlots, EzyTutorError> {
+    let timezone = query.tz.unwrap_or("UTC".to_string());
+    let minutes = validate_lesson_minutes(query.duration.unwrap_or(DEFAULT_LESSON_MINUTES))?;
+    if query.to < query.from || (query.to - query.from).num_days() >= MAX_SLOT_RANGE_DAYS {
+        return Err(EzyTutorError::InvalidInput(format!("Date range must cover between 1 and {} days", MAX_SLOT_RANGE_DAYS)));
+    }
+    known_timezone(&app_state, &timezone).await?;
+    app_state.tutors.by_id(tutor_id).await?;
+
+    let slots = dbaccess::booking::get_open_slots(&app_state.db, tutor_id, query.from, query.to, &timezone, minutes).await?;
+    Ok(OpenSlots { timezone, slots })
+}
+
+pub async fn book_lesson(app_state: web::Data<AppState>, tutor_id: Uuid, lesson_dto: BookLessonDto) -> Result<Lesson, EzyTutorError> {
+    let minutes = validate_lesson_minutes(lesson_dto.duration_minutes.unwrap_or(DEFAULT_LESSON_MINUTES))?;
+    if lesson_dto.starts_at <= Utc::now() {
+        return Err(EzyTutorError::InvalidInput("Lessons can only be booked in the future".to_string()));
+    }
+    app_state.tutors.by_id(tutor_id).await?;
+
+    let ends_at = lesson_dto.starts_at + Duration::minutes(minutes as i64);
+    dbaccess::booking::book_lesson(&app_state.db, tutor_id, lesson_dto.student_id, lesson_dto.starts_at, ends_at).await?
+        .ok_or(EzyTutorError::InvalidInput("The tutor is not available at that time".to_string()))
+}
+
+pub async fn get_lessons(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Vec<Lesson>, EzyTutorError> {
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::booking::get_upcoming_lessons(&app_state.db, tutor_id).await
+}
+
+pub async fn cancel_lesson(app_state: web::Data<AppState>, tutor_id: Uuid, lesson_id: Uuid) -> Result<Lesson, EzyTutorError> {
+    let mut uow = UnitOfWork::begin(&app_state.db).await?;
+    let lesson = dbaccess::booking::get_booked_lesson(uow.conn(), tutor_id, lesson_id).await?;
+    if !lesson.can_be_cancelled(Utc::now()) {
+        return Err(EzyTutorError::Conflict(format!("Lessons can only be cancelled up to {} hours before they start", CANCELLATION_WINDOW_HOURS)));
+    }
+    let lesson = dbaccess::booking::cancel_lesson(uow.conn(), tutor_id, lesson_id).await?;
+    uow.commit().await?;
+    Ok(lesson)
+}
@@ -0,0 +1,71 @@
+use actix_web::web;
+use chrono::Utc;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::coupon::{Coupon, CouponRedemption, CreateCouponDto, Quote, RedeemCouponDto};
+use crate::repository::CourseRepository;
+use crate::services::unit_of_work::UnitOfWork;
+use crate::state::AppState;
+
+pub async fn get_coupons(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Vec<Coupon>, EzyTutorError> {
+    dbaccess::coupon::get_coupons_by_tutor(&app_state.db, tutor_id).await
+}
+
+pub async fn create_coupon(app_state: web::Data<AppState>, tutor_id: Uuid, coupon_dto: CreateCouponDto) -> Result<Coupon, EzyTutorError> {
+    let coupon_dto = coupon_dto.validate()?;
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::coupon::create(&app_state.db, tutor_id, coupon_dto).await
+}
+
+pub async fn delete_coupon(app_state: web::Data<AppState>, tutor_id: Uuid, coupon_id: Uuid) -> Result<(), EzyTutorError> {
+    dbaccess::coupon::soft_delete(&app_state.db, tutor_id, coupon_id).await
+}
+
+async fn applicable_coupon(db: impl PgExecutor<'_>, tutor_id: Uuid, course_id: Uuid, code: &str) -> Result<Coupon, EzyTutorError> {
+    let coupon = dbaccess::coupon::by_code(db, tutor_id, code.trim()).await
+        .map_err(|err| match err {
+            EzyTutorError::NotFound(_) => EzyTutorError::NotFound("Coupon not found".to_string()),
+            err => err,
+        })?;
+
+    if !coupon.applies_to(course_id) {
+        return Err(EzyTutorError::InvalidInput("Coupon does not apply to this course".to_string()));
+    }
+    if coupon.is_expired(Utc::now().naive_utc()) || coupon.is_exhausted() {
+        return Err(EzyTutorError::Conflict("Coupon is expired or has no redemptions left".to_string()));
+    }
+    Ok(coupon)
+}
+
+async fn course_price(courses: &dyn CourseRepository, tutor_id: Uuid, course_id: Uuid) -> Result<i32, EzyTutorError> {
+    courses.get_course(tutor_id, course_id).await?
+        .price
+        .ok_or(EzyTutorError::InvalidInput("Course has no price".to_string()))
+}
+
+pub async fn quote(app_state: web::Data<AppState>, tutor_id: Uuid, course_id: Uuid, code: Option<String>) -> Result<Quote, EzyTutorError> {
+    let price = course_price(app_state.courses.as_ref(), tutor_id, course_id).await?;
+    let effective_price = match &code {
+        Some(code) => applicable_coupon(&app_state.db, tutor_id, course_id, code).await?.discounted_price(price),
+        None => price,
+    };
+
+    Ok(Quote {
+        course_id,
+        code: code.map(|code| code.trim().to_uppercase()),
+        price,
+        discount: price - effective_price,
+        effective_price,
+    })
+}
+
+pub async fn redeem(app_state: web::Data<AppState>, tutor_id: Uuid, course_id: Uuid, redeem_dto: RedeemCouponDto) -> Result<CouponRedemption, EzyTutorError> {
+    let mut uow = UnitOfWork::begin(&app_state.db).await?;
+    let price = course_price(&uow.repository(), tutor_id, course_id).await?;
+    let coupon = applicable_coupon(uow.conn(), tutor_id, course_id, &redeem_dto.code).await?;
+    let redemption = dbaccess::coupon::redeem(uow.conn(), coupon.id, course_id, redeem_dto.student_id, price, coupon.discounted_price(price)).await?;
+    uow.commit().await?;
+    Ok(redemption)
+}
@@ -0,0 +1,31 @@
+use actix_web::web;
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::credential::{Credential, CredentialDto, ReviewCredentialDto};
+use crate::state::AppState;
+
+pub async fn get_credentials(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Vec<Credential>, EzyTutorError> {
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::credential::get_credentials(&app_state.db, tutor_id).await
+}
+
+pub async fn get_pending(app_state: web::Data<AppState>) -> Result<Vec<Credential>, EzyTutorError> {
+    dbaccess::credential::get_pending_credentials(&app_state.db).await
+}
+
+pub async fn add_credential(app_state: web::Data<AppState>, tutor_id: Uuid, credential_dto: CredentialDto) -> Result<Credential, EzyTutorError> {
+    credential_dto.validate()?;
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::credential::add_credential(&app_state.db, tutor_id, credential_dto).await
+}
+
+pub async fn review(app_state: web::Data<AppState>, tutor_id: Uuid, credential_id: Uuid, review_dto: ReviewCredentialDto) -> Result<Credential, EzyTutorError> {
+    review_dto.validate()?;
+    app_state.tutors.by_id(tutor_id).await?;
+    dbaccess::credential::review_credential(&app_state.db, tutor_id, credential_id, &review_dto.status, review_dto.reason).await
+}
+
+pub async fn delete_credential(app_state: web::Data<AppState>, tutor_id: Uuid, credential_id: Uuid) -> Result<Credential, EzyTutorError> {
+    dbaccess::credential::delete_credential(&app_state.db, tutor_id, credential_id).await
+}
@@ -0,0 +1,69 @@
+use std::collections::{HashMap, HashSet};
+use actix_web::web;
+use uuid::Uuid;
+use crate::dbaccess;
+use crate::errors::EzyTutorError;
+use crate::models::course::Course;
+use crate::models::learning_path::{LearningPath, LearningPathDetail, LearningPathDto};
+use crate::state::AppState;
+
+fn validate(path_dto: &LearningPathDto) -> Result<(), EzyTutorError> {
+    if path_dto.title.trim().is_empty() {
+        return Err(EzyTutorError::InvalidInput("Learning path title can not be empty".to_string()));
+    }
+    if path_dto.course_ids.iter().collect::<HashSet<_>>().len() != path_dto.course_ids.len() {
+        return Err(EzyTutorError::InvalidInput("A course can only appear once in a learning path".to_string()));
+    }
+    Ok(())
+}
+
+/// Loads the active courses of every path with two queries, whatever the number of paths.
+async fn with_courses(app_state: &web::Data<AppState>, paths: Vec<LearningPath>) -> Result<Vec<LearningPathDetail>, EzyTutorError> {
+    let path_ids: Vec<Uuid> = paths.iter().map(|path| path.id).collect();
+    let memberships = dbaccess::learning_path::get_active_path_courses(&app_state.db, &path_ids).await?;
+    let course_ids: Vec<Uuid> = memberships.iter().map(|membership| membership.course_id).collect();
+    let courses: HashMap<Uuid, Course> = app_state.courses.get_courses_by_ids(&course_ids).await?
+        .into_iter()
+        .map(|course| (course.id, course))
+        .collect();
+
+    Ok(paths.into_iter().map(|path| {
+        let courses = memberships.iter()
+            .filter(|membership| membership.path_id == path.id)
+            .filter_map(|membership| courses.get(&membership.course_id).cloned())
+            .collect();
+        LearningPathDetail { path, courses }
+    }).collect())
+}
+
+async fn detail(app_state: &web::Data<AppState>, path: LearningPath) -> Result<LearningPathDetail, EzyTutorError> {
+    with_courses(app_state, vec![path]).await?
+        .pop()
+        .ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))
+}
+
+pub async fn get_paths(app_state: web::Data<AppState>) -> Result<Vec<LearningPathDetail>, EzyTutorError> {
+    let paths = dbaccess::learning_path::get_paths(&app_state.db).await?;
+    with_courses(&app_state, paths).await
+}
+
+pub async fn get_by_id(app_state: web::Data<AppState>, path_id: Uuid) -> Result<LearningPathDetail, EzyTutorError> {
+    let path = dbaccess::learning_path::by_id(&app_state.db, path_id).await?;
+    detail(&app_state, path).await
+}
+
+pub async fn create_path(app_state: web::Data<AppState>, path_dto: LearningPathDto) -> Result<LearningPathDetail, EzyTutorError> {
+    validate(&path_dto)?;
+    let path = dbaccess::learning_path::create(&app_state.db, path_dto).await?;
+    detail(&app_state, path).await
+}
+
+pub async fn update(app_state: web::Data<AppState>, path_dto: LearningPathDto, path_id: Uuid) -> Result<LearningPathDetail, EzyTutorError> {
+    validate(&path_dto)?;
+    let path = dbaccess::learning_path::update(&app_state.db, path_id, path_dto).await?;
+    detail(&app_state, path).await
+}
+
+pub async fn soft_delete(app_state: web::Data<AppState>, path_id: Uuid) -> Result<LearningPath, EzyTutorError> {
+    dbaccess::learning_path::soft_delete(&app_state.db, path_id).await
+}
@@ -1 +1,6 @@
-pub mod tutor_service;
\ No newline at end of file
+pub mod booking_service;
+pub mod coupon_service;
+pub mod credential_service;
+pub mod learning_path_service;
+pub mod tutor_service;
+pub mod unit_of_work;
\ No newline at end of file
@@ -1,38 +1,145 @@
+use std::collections::HashMap;
 use actix_web::web;
+use json_patch::Patch;
 use uuid::Uuid;
 use crate::dbaccess;
 use crate::errors::EzyTutorError;
-use crate::models::tutor::{CreateTutorDto, PatchTutorDto, Tutor};
+use crate::models::course::Course;
+use crate::models::duplicate::{MergeTutorDto, PossibleDuplicate, TutorMerge};
+use crate::models::include::{COURSES, IncludeQuery};
+use crate::models::slug::SlugOwner;
+use crate::models::stats::{DEFAULT_BUCKETS, MAX_BUCKETS, StatsBucket, StatsQuery, TutorStats};
+use crate::models::tutor::{CreateTutorDto, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, PatchTutorDto, RateTutorDto, Tutor, TutorCursor, TutorDetail, TutorListQuery, TutorPage, TutorRating, TutorSort};
+use crate::precondition::ExpectedVersion;
+use crate::replica::Consistency;
 use crate::state::AppState;
 
 // Try to remove web dependencies here, should be framework agnostic
-pub async fn get_tutors(app_state: web::Data<AppState>) -> Result<Vec<Tutor>, EzyTutorError>{
-    dbaccess::tutor::get_tutors(&app_state.db).await
+pub async fn get_tutors(app_state: web::Data<AppState>, query: &TutorListQuery, consistency: Consistency) -> Result<TutorPage, EzyTutorError>{
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(EzyTutorError::InvalidInput(format!("Page size must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    let name_prefix = query.name.as_deref().map(str::trim).filter(|name| !name.is_empty());
+    let sort = query.sort.unwrap_or(TutorSort::Created);
+    let after = query.after.as_deref().map(TutorCursor::decode).transpose()?;
+    if after.as_ref().is_some_and(|cursor| cursor.sort() != sort) {
+        return Err(EzyTutorError::InvalidInput("Page cursor belongs to another sort".to_string()));
+    }
+    // One extra row tells whether there is a next page
+    let mut tutors = app_state.tutors_for(consistency).get_tutors(sort, name_prefix, query.verified, after.as_ref(), limit + 1).await?;
+    let next = if tutors.len() as i64 > limit {
+        tutors.truncate(limit as usize);
+        tutors.last().map(|(_, cursor)| cursor.clone())
+    } else {
+        None
+    };
+    let tutors = tutors.into_iter().map(|(tutor, _)| tutor).collect();
+    Ok(TutorPage { tutors, next })
+}
+
+/// Embeds the courses of every tutor when `include` asks for them, translated to the first of
+/// `locales` they have a translation for, with three queries whatever the number of tutors.
+pub async fn with_courses(app_state: &web::Data<AppState>, tutors: Vec<Tutor>, include: &IncludeQuery, locales: &[String]) -> Result<Vec<TutorDetail>, EzyTutorError>{
+    if !include.wants(COURSES, &[COURSES])? {
+        return Ok(tutors.into_iter().map(|tutor| TutorDetail { tutor, courses: None }).collect());
+    }
+    let tutor_ids: Vec<Uuid> = tutors.iter().map(|tutor| tutor.id).collect();
+    let memberships = app_state.courses.get_memberships(&tutor_ids).await?;
+    let course_ids: Vec<Uuid> = memberships.iter().map(|membership| membership.course_id).collect();
+    let translations = app_state.courses.get_translations_for_courses(&course_ids, locales).await?;
+    let courses: HashMap<Uuid, Course> = app_state.courses.get_courses_by_ids(&course_ids).await?
+        .into_iter()
+        .map(|course| (course.id, course.localize(&translations, locales)))
+        .collect();
+
+    Ok(tutors.into_iter().map(|tutor| {
+        let tutor_courses = memberships.iter()
+            .filter(|membership| membership.tutor_id == tutor.id)
+            .filter_map(|membership| courses.get(&membership.course_id).cloned())
+            .collect();
+        TutorDetail { tutor, courses: Some(tutor_courses) }
+    }).collect())
+}
+
+pub async fn detail(app_state: &web::Data<AppState>, tutor: Tutor, include: &IncludeQuery, locales: &[String]) -> Result<TutorDetail, EzyTutorError>{
+    with_courses(app_state, vec![tutor], include, locales).await?
+        .pop()
+        .ok_or(EzyTutorError::NotFound("Requested resource not found".to_string()))
 }
 
 pub async fn get_by_id(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Tutor, EzyTutorError>{
-    dbaccess::tutor::by_id(&app_state.db, tutor_id).await
+    app_state.tutors.by_id(tutor_id).await
 }
 
-pub async fn create_tutor(app_state: web::Data<AppState>, tutor_dto: CreateTutorDto) -> Result<Tutor, EzyTutorError>{
-    dbaccess::tutor::create(&app_state.db, tutor_dto.clone()).await
+pub async fn get_by_slug(app_state: web::Data<AppState>, slug: &str) -> Result<Tutor, EzyTutorError>{
+    match app_state.tutors.find_slug(slug).await? {
+        SlugOwner::Tutor(tutor_id) => app_state.tutors.by_id(tutor_id).await,
+        SlugOwner::Course(_) => Err(EzyTutorError::NotFound("Requested resource not found".to_string())),
+    }
 }
 
-pub async fn update(app_state: web::Data<AppState>, tutor_dto: CreateTutorDto, tutor_id: Uuid) -> Result<Tutor, EzyTutorError>{
-    dbaccess::tutor::update(&app_state.db, tutor_dto, tutor_id).await
+/// Creates the tutor, refusing it when it looks like an existing one unless `allow_duplicate`.
+pub async fn create_tutor(app_state: web::Data<AppState>, tutor_dto: CreateTutorDto, allow_duplicate: bool) -> Result<Tutor, EzyTutorError>{
+    if !allow_duplicate {
+        let duplicates = app_state.tutors.find_duplicates(&tutor_dto.name, &tutor_dto.pic_url).await?;
+        if !duplicates.is_empty() {
+            let ids: Vec<String> = duplicates.iter().map(|tutor| tutor.id.to_string()).collect();
+            return Err(EzyTutorError::Conflict(format!("Tutor may already exist as {}", ids.join(", "))));
+        }
+    }
+    app_state.tutors.create(tutor_dto.clone()).await
 }
 
-pub async fn partial_update(app_state: web::Data<AppState>, tutor_dto: PatchTutorDto, tutor_id: Uuid) -> Result<Tutor, EzyTutorError>{
-    let due_tutor = dbaccess::tutor::by_id(&app_state.db, tutor_id).await?;
-    let updated_tutor = CreateTutorDto {
-        name: tutor_dto.name.unwrap_or(due_tutor.name.clone()),
-        pic_url: tutor_dto.pic_url.unwrap_or(due_tutor.pic_url.clone()),
-        profile: tutor_dto.profile.unwrap_or(due_tutor.profile.clone()),
-    };
-    dbaccess::tutor::update(&app_state.db, updated_tutor, tutor_id).await
+pub async fn get_possible_duplicates(app_state: web::Data<AppState>) -> Result<Vec<PossibleDuplicate>, EzyTutorError>{
+    dbaccess::duplicate::get_possible_duplicates(&app_state.db).await
 }
 
-pub async fn soft_delete(app_state: web::Data<AppState>, tutor_id: Uuid) -> Result<Tutor, EzyTutorError>{
-    dbaccess::tutor::soft_delete(&app_state.db, tutor_id).await
+pub async fn merge(app_state: web::Data<AppState>, tutor_id: Uuid, merge_dto: MergeTutorDto) -> Result<TutorMerge, EzyTutorError>{
+    if tutor_id == merge_dto.duplicate_id {
+        return Err(EzyTutorError::InvalidInput("A tutor can not be merged into itself".to_string()));
+    }
+    dbaccess::duplicate::merge(&app_state.db, tutor_id, merge_dto.duplicate_id).await
 }
 
+pub async fn update(app_state: web::Data<AppState>, tutor_dto: CreateTutorDto, tutor_id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>{
+    app_state.tutors.update(tutor_dto, tutor_id, expected).await
+}
+
+pub async fn partial_update(app_state: web::Data<AppState>, tutor_dto: PatchTutorDto, tutor_id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>{
+    tutor_dto.validate()?;
+    app_state.tutors.patch(tutor_dto, tutor_id, expected).await
+}
+
+/// Members of a tutor that a JSON Patch can test but not change.
+const READ_ONLY_MEMBERS: [&str; 7] = ["id", "created_at", "updated_at", "slug", "profile_html", "verified", "version"];
+
+pub async fn json_patch(app_state: web::Data<AppState>, patch: Patch, tutor_id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>{
+    app_state.tutors.update_with(tutor_id, Box::new(|current| {
+        expected.check(current.version)?;
+        crate::patch::apply(current, &patch, &READ_ONLY_MEMBERS)
+    })).await
+}
+
+pub async fn soft_delete(app_state: web::Data<AppState>, tutor_id: Uuid, expected: &ExpectedVersion) -> Result<Tutor, EzyTutorError>{
+    app_state.tutors.soft_delete(tutor_id, expected).await
+}
+
+pub async fn rate(app_state: web::Data<AppState>, tutor_id: Uuid, student_id: Uuid, rating_dto: RateTutorDto) -> Result<TutorRating, EzyTutorError>{
+    if !(1..=5).contains(&rating_dto.rating) {
+        return Err(EzyTutorError::InvalidInput("Rating must be between 1 and 5".to_string()));
+    }
+    app_state.tutors.by_id(tutor_id).await?;
+    app_state.tutors.rate(tutor_id, student_id, rating_dto.rating).await
+}
+
+pub async fn stats(app_state: web::Data<AppState>, tutor_id: Uuid, query: StatsQuery) -> Result<TutorStats, EzyTutorError>{
+    let buckets = query.buckets.unwrap_or(DEFAULT_BUCKETS);
+    if !(1..=MAX_BUCKETS).contains(&buckets) {
+        return Err(EzyTutorError::InvalidInput(format!("Buckets must be between 1 and {}", MAX_BUCKETS)));
+    }
+    app_state.tutors.by_id(tutor_id).await?;
+    let mut stats = dbaccess::stats::get_tutor_stats(&app_state.db, tutor_id).await?;
+    stats.activity = dbaccess::stats::get_tutor_activity(&app_state.db, tutor_id, query.bucket.unwrap_or(StatsBucket::Month), buckets).await?;
+    Ok(stats)
+}
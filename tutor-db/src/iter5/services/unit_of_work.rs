@@ -0,0 +1,111 @@
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use crate::errors::EzyTutorError;
+use crate::repository::postgres::PostgresRepository;
+
+/// Runs several `dbaccess` and repository calls as one transaction. Nothing is written until
+/// `commit`, and a unit of work dropped before it, as on an early `?` return, is rolled back as a
+/// whole.
+pub struct UnitOfWork {
+    tx: Transaction<'static, Postgres>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(pool: &PgPool) -> Result<Self, EzyTutorError> {
+        Ok(UnitOfWork { tx: pool.begin().await? })
+    }
+
+    /// The connection to hand to `dbaccess`. Functions that open their own transaction get a
+    /// savepoint inside this one.
+    pub fn conn(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+
+    /// The course and tutor repositories on this transaction, for as long as the borrow lasts.
+    pub fn repository(&mut self) -> PostgresRepository<'_> {
+        PostgresRepository::on(&mut self.tx)
+    }
+
+    pub async fn commit(self) -> Result<(), EzyTutorError> {
+        Ok(self.tx.commit().await?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use dotenv::dotenv;
+    use crate::dbaccess;
+    use crate::models::course::CreateCourseDto;
+    use crate::models::tutor::CreateTutorDto;
+    use crate::precondition::ExpectedVersion;
+    use crate::repository::{CourseRepository, TutorRepository};
+    use super::*;
+
+    fn some_tutor() -> CreateTutorDto {
+        CreateTutorDto {
+            name: "Some transactional tutor".into(),
+            pic_url: "https://i.pravatar.cc/150".into(),
+            profile: "Some profile".into(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn unit_of_work_commits_or_rolls_back_as_a_whole() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        let tutor = dbaccess::tutor::create(uow.conn(), some_tutor()).await.unwrap();
+        dbaccess::tutor::patch(uow.conn(), Default::default(), tutor.id, &ExpectedVersion::Any).await.unwrap();
+        drop(uow);
+        let resp = dbaccess::tutor::by_id(&pool, tutor.id).await;
+        assert!(matches!(resp, Err(EzyTutorError::NotFound(_))));
+
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        let tutor = dbaccess::tutor::create(uow.conn(), some_tutor()).await.unwrap();
+        let deleted = dbaccess::tutor::soft_delete(uow.conn(), tutor.id, &ExpectedVersion::Any).await.unwrap();
+        uow.commit().await.unwrap();
+        assert!(deleted.deleted_at.is_some());
+        let resp = dbaccess::tutor::by_id(&pool, tutor.id).await;
+        assert!(matches!(resp, Err(EzyTutorError::NotFound(_))));
+        assert!(dbaccess::slug::find(&pool, &deleted.slug).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn repositories_join_the_unit_of_work() {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("Database url is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let course = |tutor_id| CreateCourseDto {
+            tutor_id,
+            name: "Some transactional course".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+        };
+
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        let repository = uow.repository();
+        let tutor = repository.create(some_tutor()).await.unwrap();
+        let created = repository.new_course(course(tutor.id)).await.unwrap();
+        drop(uow);
+        let resp = PostgresRepository::new(pool.clone()).by_id(tutor.id).await;
+        assert!(matches!(resp, Err(EzyTutorError::NotFound(_))));
+        let courses = PostgresRepository::new(pool.clone()).get_courses_by_ids(&[created.id]).await.unwrap();
+        assert!(courses.is_empty());
+
+        let mut uow = UnitOfWork::begin(&pool).await.unwrap();
+        let repository = uow.repository();
+        let tutor = repository.create(some_tutor()).await.unwrap();
+        let created = repository.new_course(course(tutor.id)).await.unwrap();
+        uow.commit().await.unwrap();
+        let repository = PostgresRepository::new(pool);
+        assert_eq!(repository.by_id(tutor.id).await.unwrap().id, tutor.id);
+        assert_eq!(repository.get_course(tutor.id, created.id).await.unwrap().id, created.id);
+    }
+}
@@ -0,0 +1,40 @@
+/// Turns a name into a URL slug: accented Latin characters are transliterated (`ó` to `o`,
+/// `ñ` to `n`), anything else that is not ASCII alphanumeric becomes a single dash.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let transliterated = match c {
+            'a'..='z' | '0'..='9' => Some(c.to_string()),
+            'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => Some("a".into()),
+            'é' | 'è' | 'ê' | 'ë' => Some("e".into()),
+            'í' | 'ì' | 'î' | 'ï' => Some("i".into()),
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ø' => Some("o".into()),
+            'ú' | 'ù' | 'û' | 'ü' => Some("u".into()),
+            'ñ' => Some("n".into()),
+            'ç' => Some("c".into()),
+            'ß' => Some("ss".into()),
+            'æ' => Some("ae".into()),
+            'œ' => Some("oe".into()),
+            _ => None,
+        };
+        match transliterated {
+            Some(part) => slug.push_str(&part),
+            None if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            None => {}
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slugify_transliterates_spanish() {
+        assert_eq!(slugify("Rudimentos de la óptica"), "rudimentos-de-la-optica");
+        assert_eq!(slugify("  Año de la Señal: ¿Pingüino? "), "ano-de-la-senal-pinguino");
+        assert_eq!(slugify("Español Presocrático 😂"), "espanol-presocratico");
+        assert_eq!(slugify("😂"), "");
+    }
+}
@@ -0,0 +1,5 @@
+drop table if exists slugs;
+drop table if exists tutor_ratings;
+drop table if exists course_tutors;
+drop table if exists ezy_course_c4;
+drop table if exists tutors;
@@ -0,0 +1,97 @@
+-- The course and tutor tables of the Postgres schema, for the `sqlite` feature. Ids are 16 byte
+-- blobs and timestamps are UTC text, as sqlx encodes them.
+
+create table tutors
+(
+    id           blob         NOT NULL PRIMARY KEY,
+    name         varchar(200) NOT NULL,
+    pic_url      varchar(200) NOT NULL,
+    profile      text         NOT NULL,
+    created_at   text         NOT NULL,
+    updated_at   text         NOT NULL,
+    deleted_at   text,
+    slug         varchar(160) UNIQUE NOT NULL,
+    profile_html text         NOT NULL default '',
+    verified     boolean      NOT NULL default false
+);
+
+create index tutors_created_at_id on tutors (created_at, id) where deleted_at is null;
+create index tutors_lower_name_id on tutors (lower(name), id) where deleted_at is null;
+
+create table ezy_course_c4
+(
+    id               blob         NOT NULL PRIMARY KEY,
+    tutor_id         blob         NOT NULL,
+    name             varchar(140) NOT NULL,
+    description      text,
+    format           varchar(30),
+    structure        varchar(30),
+    duration         varchar(30),
+    price            int,
+    language         varchar(30),
+    level            varchar(30),
+    posted_time      text         NOT NULL,
+    created_at       text         NOT NULL,
+    updated_at       text         NOT NULL,
+    deleted_at       text,
+    slug             varchar(160) UNIQUE NOT NULL,
+    description_html text,
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create index ezy_course_c4_tutor_id_deleted_at on ezy_course_c4 (tutor_id, deleted_at);
+
+create table course_tutors
+(
+    course_id  blob        NOT NULL,
+    tutor_id   blob        NOT NULL,
+    role       varchar(20) NOT NULL CHECK (role in ('owner', 'co_tutor', 'assistant')),
+    created_at text        NOT NULL,
+    updated_at text        NOT NULL,
+    PRIMARY KEY (course_id, tutor_id),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create unique index course_tutors_single_owner on course_tutors (course_id) where role = 'owner';
+create index course_tutors_tutor on course_tutors (tutor_id);
+
+create table tutor_ratings
+(
+    tutor_id   blob     NOT NULL,
+    student_id blob     NOT NULL,
+    rating     smallint NOT NULL CHECK (rating between 1 and 5),
+    created_at text     NOT NULL,
+    updated_at text     NOT NULL,
+    PRIMARY KEY (tutor_id, student_id),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade
+);
+
+create table slugs
+(
+    slug       varchar(160) NOT NULL PRIMARY KEY,
+    tutor_id   blob,
+    course_id  blob,
+    created_at text         NOT NULL,
+    CHECK ((tutor_id is null) <> (course_id is null)),
+    CONSTRAINT fk_tutor
+        FOREIGN KEY (tutor_id)
+            REFERENCES tutors (id)
+            ON DELETE cascade,
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
@@ -0,0 +1,2 @@
+alter table ezy_course_c4 drop column version;
+alter table tutors drop column version;
@@ -0,0 +1,3 @@
+-- The version of tutors and courses, bumped by every update the repository writes.
+alter table tutors add column version integer NOT NULL default 1;
+alter table ezy_course_c4 add column version integer NOT NULL default 1;
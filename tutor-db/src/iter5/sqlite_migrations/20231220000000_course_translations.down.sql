@@ -0,0 +1 @@
+drop table if exists course_translations;
@@ -0,0 +1,15 @@
+-- Per-locale names and descriptions of a course, as in Postgres.
+create table course_translations
+(
+    course_id   blob         NOT NULL,
+    locale      varchar(35)  NOT NULL,
+    name        varchar(140) NOT NULL,
+    description text,
+    created_at  text         NOT NULL,
+    updated_at  text         NOT NULL,
+    PRIMARY KEY (course_id, locale),
+    CONSTRAINT fk_course
+        FOREIGN KEY (course_id)
+            REFERENCES ezy_course_c4 (id)
+            ON DELETE cascade
+);
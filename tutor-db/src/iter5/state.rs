@@ -1,8 +1,124 @@
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+#[cfg(any(test, feature = "sqlite"))]
+use std::time::Duration;
 use sqlx::postgres::PgPool;
+#[cfg(any(test, feature = "sqlite"))]
+use sqlx::postgres::PgPoolOptions;
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::SqlitePool;
+use crate::replica::Consistency;
+use crate::repository::{CourseRepository, TutorRepository};
+#[cfg(test)]
+use crate::repository::memory::InMemoryRepository;
+use crate::repository::postgres::PostgresRepository;
+#[cfg(feature = "sqlite")]
+use crate::repository::sqlite::SqliteRepository;
 
 pub struct AppState {
     pub health_check_response: String,
     pub visit_count: Mutex<u32>,
-    pub db: PgPool
-}
\ No newline at end of file
+    pub db: PgPool,
+    pub courses: Arc<dyn CourseRepository>,
+    pub tutors: Arc<dyn TutorRepository>,
+    /// What the GET endpoints read from when the client has not just written, the same
+    /// repositories as `courses` and `tutors` unless there is a replica.
+    pub replica_courses: Arc<dyn CourseRepository>,
+    pub replica_tutors: Arc<dyn TutorRepository>,
+    /// The Postgres replica behind `replica_courses` and `replica_tutors`, asked how far it has
+    /// replayed the primary before it serves a client that has written.
+    pub replica_db: Option<PgPool>,
+    /// Whether writes to tutors and courses must send `If-Match`.
+    pub require_if_match: bool,
+}
+
+impl AppState {
+    pub fn new(health_check_response: String, db: PgPool) -> Self {
+        let repository = Arc::new(PostgresRepository::new(db.clone()));
+        AppState {
+            health_check_response,
+            visit_count: Mutex::new(0),
+            db,
+            courses: repository.clone(),
+            tutors: repository.clone(),
+            replica_courses: repository.clone(),
+            replica_tutors: repository,
+            replica_db: None,
+            require_if_match: false,
+        }
+    }
+
+    /// Courses, their translations and tutors on SQLite. Everything else is only persisted by Postgres, so those
+    /// features fail with a database error.
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(health_check_response: String, pool: SqlitePool) -> Self {
+        let repository = Arc::new(SqliteRepository::new(pool));
+        AppState {
+            health_check_response,
+            visit_count: Mutex::new(0),
+            db: unavailable_pool(),
+            courses: repository.clone(),
+            tutors: repository.clone(),
+            replica_courses: repository.clone(),
+            replica_tutors: repository,
+            replica_db: None,
+            require_if_match: false,
+        }
+    }
+
+    /// Courses and tutors kept in memory, for tests that need no database. Anything else that
+    /// is persisted fails with a database error.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let repository = Arc::new(InMemoryRepository::new());
+        AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db: unavailable_pool(),
+            courses: repository.clone(),
+            tutors: repository.clone(),
+            replica_courses: repository.clone(),
+            replica_tutors: repository,
+            replica_db: None,
+            require_if_match: false,
+        }
+    }
+
+    pub fn with_if_match_required(self, require_if_match: bool) -> Self {
+        AppState { require_if_match, ..self }
+    }
+
+    /// Serves eventually consistent reads of courses and tutors from `replica`.
+    pub fn with_replica<R>(self, replica: Arc<R>) -> Self
+        where R: CourseRepository + TutorRepository + 'static {
+        AppState { replica_courses: replica.clone(), replica_tutors: replica, ..self }
+    }
+
+    /// Serves eventually consistent reads of courses and tutors from the Postgres replica behind
+    /// `pool`.
+    pub fn with_replica_pool(self, pool: PgPool) -> Self {
+        AppState { replica_db: Some(pool.clone()), ..self }.with_replica(Arc::new(PostgresRepository::new(pool)))
+    }
+
+    pub fn courses_for(&self, consistency: Consistency) -> &dyn CourseRepository {
+        match consistency {
+            Consistency::Eventual => self.replica_courses.as_ref(),
+            Consistency::ReadYourWrites => self.courses.as_ref(),
+        }
+    }
+
+    pub fn tutors_for(&self, consistency: Consistency) -> &dyn TutorRepository {
+        match consistency {
+            Consistency::Eventual => self.replica_tutors.as_ref(),
+            Consistency::ReadYourWrites => self.tutors.as_ref(),
+        }
+    }
+}
+
+/// A pool that never connects, the `.invalid` domain does not resolve.
+#[cfg(any(test, feature = "sqlite"))]
+fn unavailable_pool() -> PgPool {
+    PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(1))
+        .connect_lazy("postgres://unavailable.invalid/ezytutors")
+        .expect("Static url is valid")
+}
@@ -1,6 +1,5 @@
 use std::env;
-use std::fmt::Error;
-use actix_web::{App, HttpServer, HttpResponse, Result, web, error};
+use actix_web::{App, HttpServer, HttpResponse, web, error};
 use actix_files as fs;
 use actix_web::web::Data;
 use actix_web::web::Form;
@@ -15,14 +14,16 @@ async fn main() -> std::io::Result<()> {
     env_logger::init();
 
     let addr = env::var("SERVER_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+    let tutor_api = TutorApi::new(env::var("TUTOR_API_URL").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string()));
     debug!("Listening on: {}, check out your browser", addr);
-    HttpServer::new(|| {
+    HttpServer::new(move || {
         let tera = Tera::new(concat!(
         env!("CARGO_MANIFEST_DIR"), "/static/iter1/**/*"
         )).unwrap();
 
         App::new()
             .app_data(Data::new(tera))
+            .app_data(Data::new(tutor_api.clone()))
             .service(fs::Files::new(
                 "/static", "./static",
             ).show_files_listing())
@@ -48,9 +49,32 @@ async fn index(tmpl: web::Data<tera::Tera>) -> std::result::Result<HttpResponse,
     Ok(HttpResponse::Ok().content_type("text/html").body(s))
 }
 
+/// Client of the tutor API of `tutor-db`, at `TUTOR_API_URL`.
+#[derive(Clone)]
+pub struct TutorApi {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl TutorApi {
+    pub fn new(base_url: String) -> Self {
+        TutorApi { client: reqwest::Client::new(), base_url }
+    }
+
+    async fn get_tutors(&self) -> reqwest::Result<Vec<Tutor>> {
+        self.client.get(format!("{}/api/v1/tutors/", self.base_url))
+            .send().await?
+            .error_for_status()?
+            .json().await
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Tutor {
     name: String,
+    /// Sanitized HTML of the tutor's Markdown profile, as served by the tutor API.
+    #[serde(default)]
+    profile_html: String,
 }
 
 async fn handle_post(tmpl: web::Data<tera::Tera>, params: Form<Tutor>) -> std::result::Result<HttpResponse, actix_web::Error> {
@@ -65,13 +89,11 @@ async fn handle_post(tmpl: web::Data<tera::Tera>, params: Form<Tutor>) -> std::r
     Ok(HttpResponse::Ok().content_type("text/html").body(s))
 }
 
-async fn handle_get_tutors(tmpl: web::Data<tera::Tera>) -> std::result::Result<HttpResponse, actix_web::Error> {
-    let tutors: Vec<Tutor> = vec![
-        Tutor {name: "Micho".to_string()},
-        Tutor {name: "Tito".to_string()},
-        Tutor {name: "Negro".to_string()},
-        Tutor {name: "Cabezón".to_string()},
-    ];
+async fn handle_get_tutors(tmpl: web::Data<tera::Tera>, tutor_api: web::Data<TutorApi>) -> std::result::Result<HttpResponse, actix_web::Error> {
+    let tutors = tutor_api.get_tutors().await.map_err(|e| {
+        debug!("Tutor API error: {}", e);
+        error::ErrorBadGateway("Tutor API Error")
+    })?;
 
     let mut ctx = tera::Context::new();
     ctx.insert("tutors", &tutors);
@@ -89,12 +111,14 @@ mod tests {
     use actix_web::dev::Service;
     use actix_web::test;
     use actix_web::test::TestRequest;
+    use std::net::TcpListener;
     use super::*;
 
     #[actix_rt::test]
     async fn post_unit_test() {
         let params = Form(Tutor {
             name: "Aristoteles".to_string(),
+            profile_html: String::new(),
         });
 
         let tera = Tera::new(concat!(
@@ -113,22 +137,18 @@ mod tests {
 
     #[actix_rt::test]
     async fn post_integration_test() {
-        let params = Form(Tutor {
-            name: "Aristoteles".to_string(),
-        });
-
         let tera = Tera::new(concat!(
         env!("CARGO_MANIFEST_DIR"), "/static/iter1/**/*"
         )).unwrap();
         let webdata_tera = Data::new(tera);
 
-        let mut app = test::init_service(
+        let app = test::init_service(
             App::new().app_data(webdata_tera).configure(app_config)
         ).await;
 
         let req = TestRequest::post()
             .uri("/users")
-            .set_form(&Tutor{name: "Aristoteles".to_string()})
+            .set_form(&Tutor{name: "Aristoteles".to_string(), profile_html: String::new()})
             .to_request();
 
         let resp: ServiceResponse = app.call(req).await.unwrap();
@@ -139,4 +159,25 @@ mod tests {
             HeaderValue::from_static("text/html")
         );
     }
+    #[actix_rt::test]
+    async fn tutors_are_rendered_from_the_api() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        let api = HttpServer::new(|| App::new().route("/api/v1/tutors/", web::get().to(|| async {
+            HttpResponse::Ok().json(serde_json::json!([
+                { "id": "d709c2c9-eeb8-4b6b-a63d-25ef38c78e61", "name": "Rogerio Bacon", "profile_html": "<p>Doctor <em>Mirabilis</em></p>\n" },
+            ]))
+        }))).listen(listener).unwrap().run();
+        actix_rt::spawn(api);
+
+        let tera = Tera::new(concat!(
+        env!("CARGO_MANIFEST_DIR"), "/static/iter1/**/*"
+        )).unwrap();
+        let resp = handle_get_tutors(Data::new(tera), Data::new(TutorApi::new(api_url))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let html = std::str::from_utf8(&body).unwrap();
+        assert!(html.contains("Rogerio Bacon"));
+        assert!(html.contains("<p>Doctor <em>Mirabilis</em></p>"));
+    }
 }
\ No newline at end of file